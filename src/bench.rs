@@ -0,0 +1,110 @@
+/*
+Times the CPU Side of Rendering, Which Needs No GPU
+*/
+
+use polar_game::PolarGame;
+use rendering::PolarPrimitive;
+use rendering::primitives::polar_pixel::PolarPixelVertex;
+use rendering::primitives::polar_pixel::staging::{PolarStaging, StreamUpload};
+use gg::games::Game;
+use time;
+
+#[derive(Default)]
+struct Timing {
+    total_ns: u64,
+    worst_ns: u64,
+    uploaded_vertices: u64,
+    allocations: u64,
+}
+
+impl Timing {
+    fn add(&mut self, start: u64) {
+        let elapsed = time::precise_time_ns() - start;
+        self.total_ns += elapsed;
+        self.worst_ns = self.worst_ns.max(elapsed);
+    }
+
+    fn report(&self, name: &str, frames: u32) {
+        let frames = frames.max(1) as f64;
+        println!("{:<11} mean {:>8.1}us, worst {:>8.1}us, {:>8.0} vertices uploaded and {:.2} buffers allocated per frame",
+                 name, self.total_ns as f64 / frames / 1000.0, self.worst_ns as f64 / 1000.0,
+                 self.uploaded_vertices as f64 / frames, self.allocations as f64 / frames);
+    }
+}
+
+// Stages the way PolarBuffer does: static geometry is only uploaded when its key changes, and the
+// stream buffer is only reallocated when it runs out of room
+fn stage_persistent(staging: &mut PolarStaging, primitives: Vec<PolarPrimitive>, timing: &mut Timing) {
+    for primitive in primitives {
+        match primitive {
+            PolarPrimitive::PolarPix(pixel) => staging.get_vertices().push(PolarPixelVertex::from(pixel)),
+            PolarPrimitive::PolarBatch(pixels) => staging.extend(pixels),
+            PolarPrimitive::StaticPolar(pixels) => staging.load_static(&pixels),
+            PolarPrimitive::Unrolled(_) | PolarPrimitive::Text(_) | PolarPrimitive::Background(_)
+                | PolarPrimitive::PostEffects(_) => (),
+        }
+    }
+    if let Some(vertices) = staging.take_static_upload() {
+        timing.uploaded_vertices += vertices.len() as u64;
+        timing.allocations += 1;
+    }
+    match staging.plan_stream() {
+        StreamUpload::Nothing => (),
+        StreamUpload::Write(len) => timing.uploaded_vertices += len as u64,
+        StreamUpload::Grow(_) => {
+            timing.uploaded_vertices += staging.get_stream_vertices().len() as u64;
+            timing.allocations += 1;
+        }
+    }
+    staging.clear();
+}
+
+// Stages the way PolarBuffer did before persistent buffers: every vertex, static or not, goes into a
+// fresh Vec and a new vertex buffer each frame
+fn stage_per_frame(primitives: Vec<PolarPrimitive>, timing: &mut Timing) {
+    let mut vertices = Vec::new();
+    for primitive in primitives {
+        match primitive {
+            PolarPrimitive::PolarPix(pixel) => vertices.push(PolarPixelVertex::from(pixel)),
+            PolarPrimitive::PolarBatch(pixels) => vertices.extend(pixels.into_iter().map(PolarPixelVertex::from)),
+            PolarPrimitive::StaticPolar(pixels) => vertices.extend(pixels.pixels.iter().map(|p| PolarPixelVertex::from(*p))),
+            PolarPrimitive::Unrolled(_) | PolarPrimitive::Text(_) | PolarPrimitive::Background(_)
+                | PolarPrimitive::PostEffects(_) => (),
+        }
+    }
+    if !vertices.is_empty() {
+        timing.uploaded_vertices += vertices.len() as u64;
+        timing.allocations += 1;
+    }
+}
+
+// Plays the game with no input and stages each frame's renderables both ways, so the persistent
+// buffers can be compared against the per-frame upload path they replaced
+pub fn run_render_benchmark(mut game: PolarGame, frames: u32) {
+    let t_step = 1.0 / 60.0;
+    let mut staging = PolarStaging::new();
+    let mut persistent = Timing::default();
+    let mut per_frame = Timing::default();
+
+    game.init();
+    for _ in 0..frames {
+        game.update_input();
+        game.update_logic(t_step);
+        let primitives: Vec<PolarPrimitive> = game.build_renderables().into_iter()
+            .flat_map(|mut renderable| renderable.get_primitives())
+            .collect();
+        let copy = primitives.clone();
+
+        let start = time::precise_time_ns();
+        stage_persistent(&mut staging, primitives, &mut persistent);
+        persistent.add(start);
+
+        let start = time::precise_time_ns();
+        stage_per_frame(copy, &mut per_frame);
+        per_frame.add(start);
+    }
+
+    println!("Staged {} frames on the CPU", frames);
+    persistent.report("Persistent:", frames);
+    per_frame.report("Per-frame:", frames);
+}
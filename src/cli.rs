@@ -0,0 +1,208 @@
+/*
+Parses the Command-Line Options Used to Launch the Game
+*/
+
+use polar_game::GameMode;
+use export::{ExportOptions, ExportFormat};
+use std::fmt;
+
+pub const USAGE: &'static str = "\
+Usage: rusty_dodge_v2 [OPTIONS]
+
+Options:
+    --windowed               Run in a window
+    --fullscreen             Run fullscreen
+    --resolution <WxH>       Window resolution, e.g. 1280x720
+    --settings <PATH>        Settings file to load and save (default: settings.toml)
+    --seed <N>               Seed for the run
+    --mode <MODE>            endless, time-attack[:SECONDS], zen or daily
+    --code <CODE>            Start from a shared run code
+    --record <PATH>          Record the run's inputs to a replay file
+    --replay <PATH>          Play back a recorded replay
+    --resume <PATH>          Continue a run suspended with F5 (saved to suspended.json)
+    --practice [SECONDS]     Unranked run that can be rewound by up to SECONDS (default: 5)
+    --headless [SECONDS]     Simulate without a window (a replay runs to its end)
+    --export <PATH>          Render the --replay offscreen to a directory of PNGs, or a raw file
+    --export-format <FMT>    png (default) or raw; raw writes RGBA frames, to stdout if PATH is -
+    --export-resolution <WxH>
+                             Exported frame size (default: 1920x1080)
+    --export-fps <N>         Exported frames per second (default: 60)
+    --bench-render [FRAMES]  Time building and staging each frame's geometry on the CPU (default: 3600)
+    -h, --help               Print this help";
+
+#[derive(Clone, Debug, Default)]
+pub struct CliOptions {
+    pub fullscreen: Option<bool>,
+    pub resolution: Option<(u32, u32)>,
+    pub settings_path: Option<String>,
+    pub seed: Option<u64>,
+    pub mode: Option<GameMode>,
+    pub run_code: Option<String>,
+    pub record_path: Option<String>,
+    pub replay_path: Option<String>,
+    pub resume_path: Option<String>,
+    pub practice: Option<f64>,
+    pub headless: Option<f64>,
+    pub export: Option<ExportOptions>,
+    pub bench_render: Option<u32>,
+    pub help: bool,
+}
+
+#[derive(Debug)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\n\nRun with --help for the list of options.", self.0)
+    }
+}
+
+pub fn parse<I: Iterator<Item = String>>(args: I) -> Result<CliOptions, CliError> {
+    let mut options = CliOptions::default();
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => options.help = true,
+            "--windowed" => options.fullscreen = Some(false),
+            "--fullscreen" => options.fullscreen = Some(true),
+            "--resolution" => options.resolution = Some(parse_resolution(&arg, &value(&arg, args.next())?)?),
+            "--settings" => options.settings_path = Some(value(&arg, args.next())?),
+            "--seed" => {
+                let seed = value(&arg, args.next())?;
+                options.seed = Some(seed.parse().map_err(|_| CliError(format!("--seed expects a whole number, got '{}'", seed)))?);
+            },
+            "--mode" => options.mode = Some(parse_mode(&value(&arg, args.next())?)?),
+            "--code" => options.run_code = Some(value(&arg, args.next())?),
+            "--record" => options.record_path = Some(value(&arg, args.next())?),
+            "--replay" => options.replay_path = Some(value(&arg, args.next())?),
+            "--resume" => options.resume_path = Some(value(&arg, args.next())?),
+            "--practice" => {
+                let seconds = match args.peek() {
+                    Some(next) if !next.starts_with("--") => Some(next.clone()),
+                    _ => None,
+                };
+                options.practice = Some(match seconds {
+                    Some(s) => {
+                        args.next();
+                        match s.parse::<f64>() {
+                            Ok(l) if l > 0.0 => l,
+                            _ => return Err(CliError(format!("--practice expects a positive number of seconds, got '{}'", s))),
+                        }
+                    },
+                    None => 5.0,
+                });
+            },
+            "--headless" => {
+                let seconds = match args.peek() {
+                    Some(next) if !next.starts_with("--") => Some(next.clone()),
+                    _ => None,
+                };
+                options.headless = Some(match seconds {
+                    Some(s) => {
+                        args.next();
+                        s.parse().map_err(|_| CliError(format!("--headless expects a number of seconds, got '{}'", s)))?
+                    },
+                    None => 60.0,
+                });
+            },
+            "--bench-render" => {
+                let frames = match args.peek() {
+                    Some(next) if !next.starts_with("--") => Some(next.clone()),
+                    _ => None,
+                };
+                options.bench_render = Some(match frames {
+                    Some(f) => {
+                        args.next();
+                        f.parse().map_err(|_| CliError(format!("--bench-render expects a number of frames, got '{}'", f)))?
+                    },
+                    None => 3600,
+                });
+            },
+            "--export" => export(&mut options).output = value(&arg, args.next())?,
+            "--export-format" => {
+                let format = value(&arg, args.next())?;
+                export(&mut options).format = match format.as_str() {
+                    "png" => ExportFormat::PngSequence,
+                    "raw" => ExportFormat::Raw,
+                    _ => return Err(CliError(format!("--export-format expects png or raw, got '{}'", format))),
+                };
+            },
+            "--export-resolution" => export(&mut options).resolution = parse_resolution(&arg, &value(&arg, args.next())?)?,
+            "--export-fps" => {
+                let fps = value(&arg, args.next())?;
+                export(&mut options).fps = match fps.parse::<f64>() {
+                    Ok(f) if f > 0.0 => f,
+                    _ => return Err(CliError(format!("--export-fps expects a positive number, got '{}'", fps))),
+                };
+            },
+            other => return Err(CliError(format!("unknown option '{}'", other))),
+        }
+    }
+
+    if options.bench_render.is_some() && (options.headless.is_some() || options.export.is_some()) {
+        return Err(CliError("--bench-render cannot be combined with --headless or --export".to_string()));
+    }
+    if let Some(ref export) = options.export {
+        if export.output.is_empty() {
+            return Err(CliError("the --export-* options need --export <PATH>".to_string()));
+        }
+        if options.replay_path.is_none() {
+            return Err(CliError("--export needs a --replay to render".to_string()));
+        }
+        if options.headless.is_some() {
+            return Err(CliError("--export cannot be combined with --headless".to_string()));
+        }
+    }
+
+    if options.replay_path.is_some() && (options.run_code.is_some() || options.seed.is_some() || options.mode.is_some()) {
+        return Err(CliError("--replay cannot be combined with --code, --seed or --mode".to_string()));
+    }
+    if options.practice.is_some() && options.replay_path.is_some() {
+        return Err(CliError("--practice cannot be combined with --replay".to_string()));
+    }
+    if options.resume_path.is_some() && (options.replay_path.is_some() || options.record_path.is_some() || options.practice.is_some()
+                                         || options.run_code.is_some() || options.seed.is_some() || options.mode.is_some()) {
+        return Err(CliError("--resume cannot be combined with --replay, --record, --practice, --code, --seed or --mode".to_string()));
+    }
+    if options.run_code.is_some() && (options.seed.is_some() || options.mode.is_some()) {
+        return Err(CliError("--code already sets the seed and mode".to_string()));
+    }
+    Ok(options)
+}
+
+fn value(option: &str, next: Option<String>) -> Result<String, CliError> {
+    match next {
+        Some(v) => Ok(v),
+        None => Err(CliError(format!("{} expects a value", option))),
+    }
+}
+
+fn export(options: &mut CliOptions) -> &mut ExportOptions {
+    options.export.get_or_insert_with(ExportOptions::default)
+}
+
+fn parse_resolution(option: &str, value: &str) -> Result<(u32, u32), CliError> {
+    let error = || CliError(format!("{} expects WIDTHxHEIGHT, got '{}'", option, value));
+    let mut parts = value.split('x');
+    let width = parts.next().and_then(|w| w.parse().ok()).ok_or_else(&error)?;
+    let height = parts.next().and_then(|h| h.parse().ok()).ok_or_else(&error)?;
+    if parts.next().is_some() || width == 0 || height == 0 {
+        return Err(error());
+    }
+    Ok((width, height))
+}
+
+fn parse_mode(value: &str) -> Result<GameMode, CliError> {
+    let mut parts = value.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some("endless"), None) => Ok(GameMode::Endless),
+        (Some("zen"), None) => Ok(GameMode::Zen),
+        (Some("daily"), None) => Ok(GameMode::daily_challenge()),
+        (Some("time-attack"), None) => Ok(GameMode::TimeAttack { duration: 60.0 }),
+        (Some("time-attack"), Some(seconds)) => match seconds.parse::<f64>() {
+            Ok(d) if d > 0.0 => Ok(GameMode::TimeAttack { duration: d }),
+            _ => Err(CliError(format!("time-attack expects a positive number of seconds, got '{}'", seconds))),
+        },
+        _ => Err(CliError(format!("unknown mode '{}' (expected endless, time-attack[:SECONDS], zen or daily)", value))),
+    }
+}
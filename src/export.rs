@@ -0,0 +1,101 @@
+/*
+Exports a Replay as a PNG Sequence or Raw RGBA Video, Rendered Offscreen
+*/
+
+use polar_game::PolarGame;
+use polar_game::screenshot::CaptureAssets;
+use settings::CaptureConfig;
+use gg::games::Game;
+use gg::games::view_details::ViewDetails;
+use gg::rendering::Renderer;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExportFormat {
+    // Numbered frame-000000.png files in the output directory
+    PngSequence,
+    // Frames of width * height RGBA bytes back to back, to a file or "-" for stdout
+    Raw,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    pub output: String,
+    pub format: ExportFormat,
+    pub resolution: (u32, u32),
+    pub fps: f64,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            output: String::new(),
+            format: ExportFormat::PngSequence,
+            resolution: (1920, 1080),
+            fps: 60.0,
+        }
+    }
+}
+
+enum FrameSink {
+    Pngs(String),
+    Raw(Box<Write>),
+}
+
+impl FrameSink {
+    fn open(options: &ExportOptions) -> io::Result<FrameSink> {
+        match options.format {
+            ExportFormat::PngSequence => {
+                fs::create_dir_all(&options.output)?;
+                Ok(FrameSink::Pngs(options.output.clone()))
+            },
+            ExportFormat::Raw if options.output == "-" => Ok(FrameSink::Raw(Box::new(BufWriter::new(io::stdout())))),
+            ExportFormat::Raw => Ok(FrameSink::Raw(Box::new(BufWriter::new(File::create(&options.output)?)))),
+        }
+    }
+}
+
+// The simulation follows the replay's recorded steps so that it plays out exactly as recorded,
+// and a frame is rendered each time the simulated time passes the next frame time
+pub fn export_replay(mut game: PolarGame, options: &ExportOptions, capture: &CaptureConfig,
+                     texture_paths: &[String]) -> io::Result<u64> {
+    let (width, height) = options.resolution;
+    let frame_step = 1.0 / options.fps;
+    let mut renderer = CaptureAssets::load(capture, texture_paths).build_renderer(width, height);
+    let mut sink = FrameSink::open(options)?;
+    let mut frames: u64 = 0;
+    let mut time = 0.0;
+
+    game.init();
+    loop {
+        while frames as f64 * frame_step <= time {
+            renderer.set_worldview(ViewDetails::Polar(game.get_polar_view()));
+            renderer.load_renderables(game.build_renderables());
+            renderer.render();
+            match sink {
+                FrameSink::Pngs(ref directory) => {
+                    let path = Path::new(directory).join(format!("frame-{:06}.png", frames));
+                    renderer.save_png(&path.to_string_lossy())?;
+                },
+                FrameSink::Raw(ref mut writer) => writer.write_all(&renderer.get_rgba())?,
+            }
+            frames += 1;
+        }
+        if game.is_replay_finished() {
+            break;
+        }
+        game.update_input();
+        let step = game.get_replay_step().unwrap_or(frame_step);
+        game.update_logic(step);
+        time += step;
+    }
+
+    if let FrameSink::Raw(ref mut writer) = sink {
+        writer.flush()?;
+    }
+    Ok(frames)
+}
@@ -6,13 +6,25 @@ extern crate rand;
 #[macro_use]
 extern crate glium;
 extern crate rusttype;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+extern crate png;
 
 use gg::debug::*;
 use gg::{debug, input, window, handler_basic, Handler};
+use gg::games::Game;
 use gg::rendering::DisplaySettings;
 use std::env;
+use std::process;
 mod polar_game;
 mod rendering;
+mod settings;
+mod cli;
+mod export;
+mod bench;
 
 fn main() {
     env::set_var("RUST_BACKTRACE", "full");
@@ -20,19 +32,104 @@ fn main() {
     debug(&format!("Starting Up - Date: {}", time::now_utc().ctime()));
     let error_writer = Box::new(ErrorWriter::new());
 
-    let display_settings = DisplaySettings {
-        res: (1920, 1080),
-        fullscreen: true,
-        text_glyph_detail: 128.0,
-            ..Default::default()
+    let options = match cli::parse(env::args().skip(1)) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(2);
+        }
     };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    let settings_path = options.settings_path.clone().unwrap_or(settings::DEFAULT_SETTINGS_PATH.to_string());
+    let mut settings = settings::Settings::load(&settings_path);
+    if let Some(fullscreen) = options.fullscreen {
+        settings.display.fullscreen = fullscreen;
+    }
+    if let Some((width, height)) = options.resolution {
+        settings.display.width = width;
+        settings.display.height = height;
+    }
+    let display_settings: DisplaySettings = settings.get_display_settings();
+    let capture_config = settings.capture.clone();
+    let texture_paths = settings.appearance.textures.clone();
 
-    let renderer = Box::new(::rendering::glium_renderer::GliumRenderer::new(display_settings));
+    let mut builder = polar_game::PolarGameBuilder::default();
+    builder.with_settings(settings, &settings_path);
+    if let Some(ref code) = options.run_code {
+        if let Err(e) = builder.with_run_code(code) {
+            eprintln!("Error: could not start from run code: {}", e);
+            process::exit(2);
+        }
+    }
+    if let Some(mode) = options.mode {
+        builder.with_mode(mode);
+    }
+    if let Some(seed) = options.seed {
+        builder.with_seed(seed);
+    }
+    if let Some(length) = options.practice {
+        builder.with_practice_mode(true).with_rewind_length(length);
+    }
+    if let Some(ref path) = options.replay_path {
+        match polar_game::replay::Replay::load(path) {
+            Ok(replay) => {
+                eprintln!("Playing back {} ({:.1}s)", path, replay.get_duration());
+                builder.with_replay(replay);
+            },
+            Err(e) => {
+                eprintln!("Error: could not load replay: {}", e);
+                process::exit(2);
+            }
+        }
+    }
+    if let Some(ref path) = options.resume_path {
+        match polar_game::snapshot::GameSnapshot::load(path) {
+            Ok(snapshot) => { builder.with_snapshot(snapshot); },
+            Err(e) => {
+                eprintln!("Error: could not resume run: {}", e);
+                process::exit(2);
+            }
+        }
+    }
+    if let Some(ref path) = options.record_path {
+        builder.with_recording(path);
+    }
+    let game = builder.build_game();
+
+    if let Some(frames) = options.bench_render {
+        bench::run_render_benchmark(game, frames);
+        return;
+    }
+
+    if let Some(ref export_options) = options.export {
+        match export::export_replay(game, export_options, &capture_config, &texture_paths) {
+            Ok(frames) => eprintln!("Exported {} frames to {}", frames, export_options.output),
+            Err(e) => {
+                eprintln!("Error: export failed: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(seconds) = options.headless {
+        run_headless(game, seconds);
+        return;
+    }
+
+    let mut renderer = Box::new(::rendering::glium_renderer::GliumRenderer::new(display_settings));
+    match ::rendering::assets::load_texture_layers(&texture_paths) {
+        Ok(layers) => { renderer.with_textures(&layers); },
+        Err(e) => debug(&format!("Warning: {}, textured pixels will show their tint only", e)),
+    }
     let input_handler: Box<input::InputHandler> = Box::new(input::multihandler::MultiInput::new());
     let window_handler: Box<window::WindowHandler> = Box::new(window::GlutinInput::new());
 
-    let game = Box::new(polar_game::PolarGameBuilder::default().build_game());
-    let mut handler: Box<Handler> = Box::new(handler_basic::HandlerBasic::new(renderer, input_handler, window_handler, game));
+    let mut handler: Box<Handler> = Box::new(handler_basic::HandlerBasic::new(renderer, input_handler, window_handler, Box::new(game)));
 
     handler.init();
     while !handler.should_exit() {
@@ -44,3 +141,20 @@ fn main() {
     }
     handler.on_exit();
 }
+
+// Steps the simulation at a fixed rate without a window; a replay runs to its end instead
+fn run_headless(mut game: polar_game::PolarGame, seconds: f64) {
+    let t_step = 1.0 / 60.0;
+    let replaying = !game.is_replay_finished();
+    let mut elapsed = 0.0;
+    game.init();
+    loop {
+        let finished = if replaying { game.is_replay_finished() } else { elapsed >= seconds || game.is_over() };
+        if finished { break; }
+        game.update_input();
+        game.update_logic(t_step);
+        elapsed += t_step;
+    }
+    game.on_exit();
+    println!("Simulated {0:.2}s - Score: {1:.2} - Run Code: {2}", elapsed, game.get_score(), game.get_run_code());
+}
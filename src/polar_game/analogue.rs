@@ -0,0 +1,173 @@
+/*
+Shapes Gamepad Stick Input with Deadzones, Response Curves and Sensitivity
+*/
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    Linear,
+    Quadratic,
+    // Output values at evenly spaced inputs from 0.0 to 1.0, interpolated linearly
+    Custom(Vec<f64>),
+}
+
+impl ResponseCurve {
+    pub fn apply(&self, input: f64) -> f64 {
+        let x = input.max(0.0).min(1.0);
+        match *self {
+            ResponseCurve::Linear => x,
+            ResponseCurve::Quadratic => x * x,
+            ResponseCurve::Custom(ref points) => {
+                if points.len() < 2 {
+                    return x;
+                }
+                let scaled = x * (points.len() - 1) as f64;
+                let index = (scaled.floor() as usize).min(points.len() - 2);
+                let fraction = scaled - index as f64;
+                points[index] + (points[index + 1] - points[index]) * fraction
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisSettings {
+    pub sensitivity: f64,
+    pub invert: bool,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        AxisSettings {
+            sensitivity: 1.0,
+            invert: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StickSettings {
+    pub radial_deadzone: f64,
+    pub axial_deadzone: f64,
+    pub curve: ResponseCurve,
+    pub x_axis: AxisSettings,
+    pub y_axis: AxisSettings,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        StickSettings {
+            radial_deadzone: 0.1,
+            axial_deadzone: 0.0,
+            curve: ResponseCurve::Linear,
+            x_axis: AxisSettings::default(),
+            y_axis: AxisSettings::default(),
+        }
+    }
+}
+
+fn rescale_deadzone(value: f64, deadzone: f64) -> f64 {
+    if value <= deadzone || deadzone >= 1.0 {
+        0.0
+    } else {
+        ((value - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+impl StickSettings {
+    // Maps a raw stick position to an output in [-1, 1] on each axis; the radial deadzone and
+    // curve act on the stick's magnitude so diagonals keep their direction
+    pub fn map(&self, raw_x: f64, raw_y: f64) -> (f64, f64) {
+        let x = if raw_x.abs() > self.axial_deadzone { raw_x } else { 0.0 };
+        let y = if raw_y.abs() > self.axial_deadzone { raw_y } else { 0.0 };
+        let magnitude = (x * x + y * y).sqrt();
+        if magnitude == 0.0 {
+            return (0.0, 0.0);
+        }
+        let shaped = self.curve.apply(rescale_deadzone(magnitude, self.radial_deadzone));
+        let scale = shaped / magnitude;
+        (self.apply_axis(&self.x_axis, x * scale), self.apply_axis(&self.y_axis, y * scale))
+    }
+
+    fn apply_axis(&self, axis: &AxisSettings, value: f64) -> f64 {
+        let value = if axis.invert { -value } else { value };
+        (value * axis.sensitivity).max(-1.0).min(1.0)
+    }
+}
+
+// Keyboard and stick input add together rather than one overriding the other
+pub fn blend(keyboard: f64, stick: f64) -> f64 {
+    (keyboard + stick).max(-1.0).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn curves_map_the_unit_interval() {
+        assert!(close(ResponseCurve::Linear.apply(0.5), 0.5));
+        assert!(close(ResponseCurve::Quadratic.apply(0.5), 0.25));
+        let custom = ResponseCurve::Custom(vec![0.0, 0.1, 1.0]);
+        assert!(close(custom.apply(0.25), 0.05));
+        assert!(close(custom.apply(0.75), 0.55));
+        assert!(close(custom.apply(1.0), 1.0));
+        assert!(close(custom.apply(2.0), 1.0));
+    }
+
+    #[test]
+    fn radial_deadzone_rescales_to_full_range() {
+        let stick = StickSettings { radial_deadzone: 0.2, ..Default::default() };
+        assert_eq!(stick.map(0.1, 0.1), (0.0, 0.0));
+        let (x, y) = stick.map(0.6, 0.0);
+        assert!(close(x, 0.5));
+        assert!(close(y, 0.0));
+        let (x, _) = stick.map(1.0, 0.0);
+        assert!(close(x, 1.0));
+    }
+
+    #[test]
+    fn radial_deadzone_keeps_direction() {
+        let stick = StickSettings { radial_deadzone: 0.2, ..Default::default() };
+        let (x, y) = stick.map(0.5, 0.5);
+        assert!(close(x, y));
+        assert!(x > 0.0);
+    }
+
+    #[test]
+    fn axial_deadzone_removes_drift_on_one_axis() {
+        let stick = StickSettings { radial_deadzone: 0.0, axial_deadzone: 0.15, ..Default::default() };
+        let (x, y) = stick.map(0.8, 0.1);
+        assert!(close(x, 0.8));
+        assert_eq!(y, 0.0);
+    }
+
+    #[test]
+    fn sensitivity_and_invert_apply_per_axis() {
+        let stick = StickSettings {
+            radial_deadzone: 0.0,
+            x_axis: AxisSettings { sensitivity: 2.0, invert: false },
+            y_axis: AxisSettings { sensitivity: 1.0, invert: true },
+            ..Default::default()
+        };
+        let (x, _) = stick.map(0.3, 0.0);
+        assert!(close(x, 0.6));
+        let (x, _) = stick.map(0.8, 0.0);
+        assert!(close(x, 1.0));
+        let (_, y) = stick.map(0.0, 0.4);
+        assert!(close(y, -0.4));
+    }
+
+    #[test]
+    fn blend_adds_and_clamps() {
+        assert!(close(blend(1.0, 0.0), 1.0));
+        assert!(close(blend(0.0, -0.3), -0.3));
+        assert!(close(blend(1.0, 0.5), 1.0));
+        assert!(close(blend(1.0, -0.4), 0.6));
+    }
+}
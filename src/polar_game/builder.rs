@@ -1,9 +1,36 @@
 use super::{PolarGame, GameSetup};
+use super::replay::Replay;
+use super::snapshot::GameSnapshot;
 use super::object::Point;
+use super::game_mode::GameMode;
+use super::Difficulty;
+use super::run_code;
+use super::run_code::RunCodeError;
+use ::settings::{Settings, DEFAULT_SETTINGS_PATH};
+use gg::debug::*;
+use rand;
+use rand::Rng;
 
-#[derive(Default)]
 pub struct PolarGameBuilder {
-    setup: GameSetup
+    setup: GameSetup,
+    settings: Settings,
+    settings_path: String,
+    replay: Option<Replay>,
+    snapshot: Option<GameSnapshot>,
+    record_path: Option<String>
+}
+
+impl Default for PolarGameBuilder {
+    fn default() -> Self {
+        PolarGameBuilder {
+            setup: GameSetup::default(),
+            settings: Settings::default(),
+            settings_path: DEFAULT_SETTINGS_PATH.to_string(),
+            replay: None,
+            snapshot: None,
+            record_path: None
+        }
+    }
 }
 
 impl PolarGameBuilder {
@@ -22,7 +49,119 @@ impl PolarGameBuilder {
         self
     }
     
+    pub fn with_hitbox_forgiveness<'a> (&'a mut self, forgiveness: f64) -> &'a mut Self {
+        self.setup.hitbox_forgiveness = forgiveness.max(0.0).min(1.0);
+        self
+    }
+
+    pub fn with_hitbox_overlay<'a> (&'a mut self, show: bool) -> &'a mut Self {
+        self.setup.show_hitboxes = show;
+        self
+    }
+
+    // Speeds of zero or below would stop or reverse time, so they are ignored
+    pub fn with_game_speed<'a> (&'a mut self, speed: f64) -> &'a mut Self {
+        if speed > 0.0 {
+            self.setup.game_speed = speed;
+        }
+        else {
+            debug(&format!("Ignoring non-positive game speed {}", speed));
+        }
+        self
+    }
+
+    pub fn with_seed<'a> (&'a mut self, seed: u64) -> &'a mut Self {
+        self.setup.seed = Some(seed);
+        self
+    }
+
+    pub fn with_practice_mode<'a> (&'a mut self, practice: bool) -> &'a mut Self {
+        self.setup.practice_mode = practice;
+        self
+    }
+
+    // A rewind window of zero or below would keep nothing to step back to, so it is ignored
+    pub fn with_rewind_length<'a> (&'a mut self, seconds: f64) -> &'a mut Self {
+        if seconds > 0.0 {
+            self.setup.rewind_length = seconds;
+        }
+        else {
+            debug(&format!("Ignoring non-positive rewind length {}", seconds));
+        }
+        self
+    }
+
+    pub fn with_mode<'a> (&'a mut self, mode: GameMode) -> &'a mut Self {
+        self.setup.mode = mode;
+        if let Some(seed) = mode.get_seed() {
+            self.setup.seed = Some(seed);
+        }
+        self
+    }
+
+    pub fn with_difficulty<'a> (&'a mut self, difficulty: Difficulty) -> &'a mut Self {
+        self.setup.difficulty = difficulty;
+        self
+    }
+
+    pub fn with_run_code<'a> (&'a mut self, code: &str) -> Result<&'a mut Self, RunCodeError> {
+        let (setup, seed) = run_code::decode(code)?;
+        self.setup.radial_max = setup.radial_max;
+        self.setup.player_start = setup.player_start;
+        self.setup.player_width = setup.player_width;
+        self.setup.tunnel_mode = setup.tunnel_mode;
+        self.setup.game_speed = setup.game_speed;
+        self.setup.hitbox_forgiveness = setup.hitbox_forgiveness;
+        self.setup.mode = setup.mode;
+        self.setup.difficulty = setup.difficulty;
+        self.setup.seed = Some(seed);
+        Ok(self)
+    }
+
+    pub fn with_settings<'a> (&'a mut self, settings: Settings, path: &str) -> &'a mut Self {
+        self.setup.game_speed = settings.gameplay.game_speed;
+        self.setup.hitbox_forgiveness = settings.gameplay.hitbox_forgiveness;
+        self.setup.show_hitboxes = settings.gameplay.show_hitboxes;
+        self.setup.tunnel_mode = settings.gameplay.tunnel_mode;
+        self.setup.practice_mode = settings.gameplay.practice_mode;
+        self.settings = settings;
+        self.settings_path = path.to_string();
+        self
+    }
+
+    pub fn with_replay<'a> (&'a mut self, replay: Replay) -> &'a mut Self {
+        self.setup = replay.setup;
+        self.replay = Some(replay);
+        self
+    }
+
+    // Continues a suspended run, which brings its own setup
+    pub fn with_snapshot<'a> (&'a mut self, snapshot: GameSnapshot) -> &'a mut Self {
+        self.setup = snapshot.setup;
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    // Recorded runs need a fixed seed so that resets replay the same way
+    pub fn with_recording<'a> (&'a mut self, path: &str) -> &'a mut Self {
+        if self.setup.seed.is_none() {
+            self.setup.seed = Some(rand::thread_rng().gen());
+        }
+        self.record_path = Some(path.to_string());
+        self
+    }
+
     pub fn build_game(&mut self) -> PolarGame {
-        PolarGame::new(self.setup)
+        let mut game = PolarGame::new(self.setup, self.settings.clone(), self.settings_path.clone());
+        if let Some(ref path) = self.record_path {
+            game.record_to(path);
+        }
+        if let Some(ref replay) = self.replay {
+            game.play_replay(replay.clone());
+        }
+        if let Some(ref snapshot) = self.snapshot {
+            game.restore(snapshot.clone());
+        }
+        game
     }
 }
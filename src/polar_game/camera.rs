@@ -0,0 +1,173 @@
+/*
+Camera Modes and Effects Applied to the Polar View
+*/
+
+use super::player::Player;
+use super::object::Object;
+use gg::games::view_details::PolarViewDetails;
+use std::f64::consts::PI;
+
+const SHAKE_DURATION: f64 = 0.6;
+const SHAKE_ANGLE: f64 = 0.01;
+const SHAKE_RADIAL: f64 = 0.05;
+const PULSE_DURATION: f64 = 0.3;
+const PULSE_ZOOM: f64 = 0.08;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CameraMode {
+    // World orientation never changes; only the radial position follows the player
+    Fixed,
+    FollowRotation,
+    Follow,
+    Smoothed,
+}
+
+impl CameraMode {
+    pub fn get_name(&self) -> &'static str {
+        match *self {
+            CameraMode::Fixed => "Fixed",
+            CameraMode::FollowRotation => "Follow Rotation",
+            CameraMode::Follow => "Follow",
+            CameraMode::Smoothed => "Smoothed Follow",
+        }
+    }
+
+    pub fn next(&self, direction: i32) -> CameraMode {
+        let modes = [CameraMode::Fixed, CameraMode::FollowRotation, CameraMode::Follow, CameraMode::Smoothed];
+        let index = modes.iter().position(|m| m == self).unwrap_or(2) as i32;
+        modes[(index + direction).max(0).min(modes.len() as i32 - 1) as usize]
+    }
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    pub mode: CameraMode,
+    // How quickly the smoothed camera catches up, per second
+    pub smoothing: f64,
+    pub screen_shake: bool,
+    pub zoom_pulses: bool,
+    // Turns per second added to the view rotation
+    pub rotation_drift: f64,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            mode: CameraMode::Follow,
+            smoothing: 6.0,
+            screen_shake: true,
+            zoom_pulses: true,
+            rotation_drift: 0.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct CameraTarget {
+    radial_shift: f64,
+    rotation_angle: f64,
+    length_total: f64,
+}
+
+pub struct Camera {
+    pub settings: CameraSettings,
+    anchor_radial: f64,
+    current: Option<CameraTarget>,
+    drift: f64,
+    shake: f64,
+    pulse: f64,
+    time: f64,
+}
+
+impl Camera {
+    // The anchor is the radial position held by camera modes that do not follow radially
+    pub fn new(settings: CameraSettings, anchor_radial: f64) -> Camera {
+        Camera {
+            settings: settings,
+            anchor_radial: anchor_radial,
+            current: None,
+            drift: 0.0,
+            shake: 0.0,
+            pulse: 0.0,
+            time: 0.0,
+        }
+    }
+
+    // Jumps straight to the player on the next update instead of easing across
+    pub fn snap(&mut self) {
+        self.current = None;
+    }
+
+    pub fn reset(&mut self) {
+        self.snap();
+        self.shake = 0.0;
+        self.pulse = 0.0;
+    }
+
+    pub fn shake(&mut self) {
+        if self.settings.screen_shake {
+            self.shake = 1.0;
+        }
+    }
+
+    pub fn pulse(&mut self) {
+        if self.settings.zoom_pulses {
+            self.pulse = 1.0;
+        }
+    }
+
+    pub fn update(&mut self, view: &mut PolarViewDetails, player: &Player, real_step: f64) {
+        self.time += real_step;
+        self.drift = (self.drift + self.settings.rotation_drift * real_step) % 1.0;
+        self.shake = (self.shake - real_step / SHAKE_DURATION).max(0.0);
+        self.pulse = (self.pulse - real_step / PULSE_DURATION).max(0.0);
+
+        let target = self.get_target(player);
+        let current = match self.current {
+            Some(current) if self.settings.mode == CameraMode::Smoothed => {
+                let amount = 1.0 - (-self.settings.smoothing * real_step).exp();
+                let mut rotation_diff = target.rotation_angle - current.rotation_angle;
+                rotation_diff -= rotation_diff.round();
+                CameraTarget {
+                    radial_shift: current.radial_shift + (target.radial_shift - current.radial_shift) * amount,
+                    rotation_angle: current.rotation_angle + rotation_diff * amount,
+                    length_total: current.length_total + (target.length_total - current.length_total) * amount,
+                }
+            },
+            _ => target
+        };
+        self.current = Some(current);
+
+        // Shake uses fixed frequencies rather than the game rng so that runs stay reproducible
+        let shake = self.shake * self.shake;
+        let shake_angle = (self.time * 2.0 * PI * 23.0).sin() * SHAKE_ANGLE * shake;
+        let shake_radial = (self.time * 2.0 * PI * 17.0).sin() * SHAKE_RADIAL * shake;
+        // Pulses pull the tunnel in and, in flat mode, push the view outwards
+        let pulse = PULSE_ZOOM * (self.pulse * PI).sin();
+
+        view.radial_shift = current.radial_shift + shake_radial + pulse;
+        view.rotation_angle = current.rotation_angle + self.drift + shake_angle;
+        view.length_total = (current.length_total * (1.0 - pulse)).max(view.length_circle);
+    }
+
+    fn get_target(&self, player: &Player) -> CameraTarget {
+        let position = player.get_position();
+        let follow_rotation = position.y + 0.25 + player.get_width() / 2.0;
+        let follow_radial = CameraTarget {
+            radial_shift: position.x - 0.75,
+            rotation_angle: follow_rotation,
+            length_total: (position.x + 0.25).max(1.0),
+        };
+        let anchored = CameraTarget {
+            radial_shift: self.anchor_radial - 0.75,
+            rotation_angle: follow_rotation,
+            length_total: (self.anchor_radial + 0.25).max(1.0),
+        };
+        match self.settings.mode {
+            CameraMode::Fixed => CameraTarget { rotation_angle: 0.0, ..follow_radial },
+            CameraMode::FollowRotation => anchored,
+            CameraMode::Follow | CameraMode::Smoothed => follow_radial,
+        }
+    }
+}
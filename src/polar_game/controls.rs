@@ -0,0 +1,281 @@
+/*
+Maps Logical Actions onto Keyboard Keys and Gamepad Axes or Buttons
+*/
+
+use super::ExternalInput;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Pause,
+    Reset,
+    Confirm,
+    Rewind,
+    Settings,
+    ToggleView,
+    ToggleUnrolled,
+    Screenshot,
+    PhotoMode,
+    ZoomIn,
+    ZoomOut,
+    CycleTheme,
+    Suspend,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Key {
+    Up, Down, Left, Right,
+    Space, Enter, Escape, LShift,
+    A, B, C, D, E, F, G, H, I, J, K, L, M,
+    N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Button {
+    A,
+    B,
+    X,
+    Y,
+    Start,
+    Back,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Binding {
+    Key(Key),
+    Axis { axis: Axis, positive: bool },
+    Button(Button),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlBindings {
+    pub move_up: Vec<Binding>,
+    pub move_down: Vec<Binding>,
+    pub move_left: Vec<Binding>,
+    pub move_right: Vec<Binding>,
+    pub pause: Vec<Binding>,
+    pub reset: Vec<Binding>,
+    pub confirm: Vec<Binding>,
+    pub rewind: Vec<Binding>,
+    pub settings: Vec<Binding>,
+    pub toggle_view: Vec<Binding>,
+    pub toggle_unrolled: Vec<Binding>,
+    pub screenshot: Vec<Binding>,
+    pub photo_mode: Vec<Binding>,
+    pub zoom_in: Vec<Binding>,
+    pub zoom_out: Vec<Binding>,
+    pub cycle_theme: Vec<Binding>,
+    pub suspend: Vec<Binding>,
+}
+
+impl Default for ControlBindings {
+    fn default() -> Self {
+        ControlBindings {
+            move_up: vec![Binding::Key(Key::Up), Binding::Axis { axis: Axis::Y, positive: true }],
+            move_down: vec![Binding::Key(Key::Down), Binding::Axis { axis: Axis::Y, positive: false }],
+            move_left: vec![Binding::Key(Key::Left), Binding::Axis { axis: Axis::X, positive: false }],
+            move_right: vec![Binding::Key(Key::Right), Binding::Axis { axis: Axis::X, positive: true }],
+            pause: vec![Binding::Key(Key::P), Binding::Button(Button::Start)],
+            reset: vec![Binding::Key(Key::R), Binding::Button(Button::Y)],
+            confirm: vec![Binding::Key(Key::Enter), Binding::Button(Button::A)],
+            rewind: vec![Binding::Key(Key::B), Binding::Button(Button::B)],
+            settings: vec![Binding::Key(Key::O), Binding::Button(Button::Back)],
+            toggle_view: vec![Binding::Key(Key::V), Binding::Button(Button::X)],
+            toggle_unrolled: vec![Binding::Key(Key::U)],
+            screenshot: vec![Binding::Key(Key::C)],
+            photo_mode: vec![Binding::Key(Key::F)],
+            zoom_in: vec![Binding::Key(Key::E)],
+            zoom_out: vec![Binding::Key(Key::Q)],
+            cycle_theme: vec![Binding::Key(Key::T)],
+            suspend: vec![Binding::Key(Key::F5)],
+        }
+    }
+}
+
+pub const ACTIONS: [Action; 17] = [Action::MoveUp, Action::MoveDown, Action::MoveLeft, Action::MoveRight,
+                                   Action::Pause, Action::Reset, Action::Confirm, Action::Rewind,
+                                   Action::Settings, Action::ToggleView, Action::ToggleUnrolled, Action::Screenshot,
+                                   Action::PhotoMode, Action::ZoomIn, Action::ZoomOut, Action::CycleTheme,
+                                   Action::Suspend];
+
+impl ControlBindings {
+    pub fn get(&self, action: Action) -> &Vec<Binding> {
+        match action {
+            Action::MoveUp => &self.move_up,
+            Action::MoveDown => &self.move_down,
+            Action::MoveLeft => &self.move_left,
+            Action::MoveRight => &self.move_right,
+            Action::Pause => &self.pause,
+            Action::Reset => &self.reset,
+            Action::Confirm => &self.confirm,
+            Action::Rewind => &self.rewind,
+            Action::Settings => &self.settings,
+            Action::ToggleView => &self.toggle_view,
+            Action::ToggleUnrolled => &self.toggle_unrolled,
+            Action::Screenshot => &self.screenshot,
+            Action::PhotoMode => &self.photo_mode,
+            Action::ZoomIn => &self.zoom_in,
+            Action::ZoomOut => &self.zoom_out,
+            Action::CycleTheme => &self.cycle_theme,
+            Action::Suspend => &self.suspend,
+        }
+    }
+
+    pub fn get_mut(&mut self, action: Action) -> &mut Vec<Binding> {
+        match action {
+            Action::MoveUp => &mut self.move_up,
+            Action::MoveDown => &mut self.move_down,
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::Pause => &mut self.pause,
+            Action::Reset => &mut self.reset,
+            Action::Confirm => &mut self.confirm,
+            Action::Rewind => &mut self.rewind,
+            Action::Settings => &mut self.settings,
+            Action::ToggleView => &mut self.toggle_view,
+            Action::ToggleUnrolled => &mut self.toggle_unrolled,
+            Action::Screenshot => &mut self.screenshot,
+            Action::PhotoMode => &mut self.photo_mode,
+            Action::ZoomIn => &mut self.zoom_in,
+            Action::ZoomOut => &mut self.zoom_out,
+            Action::CycleTheme => &mut self.cycle_theme,
+            Action::Suspend => &mut self.suspend,
+        }
+    }
+
+    // 1.0 if any key or button bound to the action is held
+    pub fn get_digital(&self, action: Action, input: &ExternalInput) -> f64 {
+        let held = self.get(action).iter().any(|b| match *b {
+            Binding::Key(key) => key_pressed(key, input),
+            Binding::Button(button) => button_pressed(button, input),
+            Binding::Axis { .. } => false,
+        });
+        if held { 1.0 } else { 0.0 }
+    }
+
+    // The strongest axis bound to the action, read from an already shaped stick position
+    pub fn get_analogue(&self, action: Action, stick: (f64, f64)) -> f64 {
+        self.get(action).iter()
+            .map(|b| match *b {
+                Binding::Axis { axis, positive } => axis_value(axis, positive, stick),
+                _ => 0.0,
+            })
+            .fold(0.0, f64::max)
+    }
+
+    // Names the first key or button bound to the action, for on-screen prompts
+    pub fn get_prompt(&self, action: Action) -> Option<String> {
+        self.get(action).iter().filter_map(|b| match *b {
+            Binding::Key(key) => Some(format!("{:?}", key).replace("Num", "")),
+            Binding::Button(button) => Some(format!("{:?} Button", button)),
+            Binding::Axis { .. } => None,
+        }).next()
+    }
+
+    pub fn is_pressed(&self, action: Action, input: &ExternalInput) -> bool {
+        let stick = (input.gamepad.get_x_axis(), input.gamepad.get_y_axis());
+        self.get_digital(action, input) > 0.5 || self.get_analogue(action, stick) > 0.5
+    }
+}
+
+// True only on the first update a held input is seen, tracked through the caller's lock
+pub fn rising_edge(pressed: bool, lock: &mut bool) -> bool {
+    let rising = pressed && !*lock;
+    *lock = pressed;
+    rising
+}
+
+fn axis_value(axis: Axis, positive: bool, stick: (f64, f64)) -> f64 {
+    let value = match axis {
+        Axis::X => stick.0,
+        Axis::Y => stick.1,
+    };
+    let directed = if positive { value } else { -value };
+    directed.max(0.0).min(1.0)
+}
+
+fn key_pressed(key: Key, input: &ExternalInput) -> bool {
+    let kbd = &input.kbd;
+    match key {
+        Key::Up => kbd.get_up(),
+        Key::Down => kbd.get_down(),
+        Key::Left => kbd.get_left(),
+        Key::Right => kbd.get_right(),
+        Key::Space => kbd.get_space(),
+        Key::Enter => kbd.get_enter(),
+        Key::Escape => kbd.get_escape(),
+        Key::LShift => kbd.get_lshift(),
+        Key::A => kbd.get_a(),
+        Key::B => kbd.get_b(),
+        Key::C => kbd.get_c(),
+        Key::D => kbd.get_d(),
+        Key::E => kbd.get_e(),
+        Key::F => kbd.get_f(),
+        Key::G => kbd.get_g(),
+        Key::H => kbd.get_h(),
+        Key::I => kbd.get_i(),
+        Key::J => kbd.get_j(),
+        Key::K => kbd.get_k(),
+        Key::L => kbd.get_l(),
+        Key::M => kbd.get_m(),
+        Key::N => kbd.get_n(),
+        Key::O => kbd.get_o(),
+        Key::P => kbd.get_p(),
+        Key::Q => kbd.get_q(),
+        Key::R => kbd.get_r(),
+        Key::S => kbd.get_s(),
+        Key::T => kbd.get_t(),
+        Key::U => kbd.get_u(),
+        Key::V => kbd.get_v(),
+        Key::W => kbd.get_w(),
+        Key::X => kbd.get_x(),
+        Key::Y => kbd.get_y(),
+        Key::Z => kbd.get_z(),
+        Key::Num0 => kbd.get_num0(),
+        Key::Num1 => kbd.get_num1(),
+        Key::Num2 => kbd.get_num2(),
+        Key::Num3 => kbd.get_num3(),
+        Key::Num4 => kbd.get_num4(),
+        Key::Num5 => kbd.get_num5(),
+        Key::Num6 => kbd.get_num6(),
+        Key::Num7 => kbd.get_num7(),
+        Key::Num8 => kbd.get_num8(),
+        Key::Num9 => kbd.get_num9(),
+        Key::F1 => kbd.get_f1(),
+        Key::F2 => kbd.get_f2(),
+        Key::F3 => kbd.get_f3(),
+        Key::F4 => kbd.get_f4(),
+        Key::F5 => kbd.get_f5(),
+        Key::F6 => kbd.get_f6(),
+        Key::F7 => kbd.get_f7(),
+        Key::F8 => kbd.get_f8(),
+        Key::F9 => kbd.get_f9(),
+        Key::F10 => kbd.get_f10(),
+        Key::F11 => kbd.get_f11(),
+        Key::F12 => kbd.get_f12(),
+    }
+}
+
+fn button_pressed(button: Button, input: &ExternalInput) -> bool {
+    let gamepad = &input.gamepad;
+    match button {
+        Button::A => gamepad.get_a(),
+        Button::B => gamepad.get_b(),
+        Button::X => gamepad.get_x(),
+        Button::Y => gamepad.get_y(),
+        Button::Start => gamepad.get_start(),
+        Button::Back => gamepad.get_back(),
+    }
+}
@@ -7,11 +7,16 @@ use super::object::{Part,Object,Point};
 use super::enemy::Enemy;
 use super::player::Player;
 
-#[derive(Copy,Clone)]
+// Fraction of the flare's length, from the head back, that can hit; the tail fades into its trail colour
+const HITBOX_LENGTH: f64 = 0.75;
+
+#[derive(Copy,Clone,Serialize,Deserialize)]
 pub struct Flare{
     position: Point,
     part: Part,
     velocity: f64,
+    id: u64,
+    grazed: bool,
 }
 
 impl Object for Flare{
@@ -31,17 +36,37 @@ impl Object for Flare{
         };
         vec![p_shift]
     }
+
+    fn get_collision_parts(&self) -> Vec<Part>{
+        let mut part = self.get_render_parts()[0];
+        part.radial.x = part.radial.y - (part.radial.y - part.radial.x) * HITBOX_LENGTH;
+        vec![part]
+    }
 }
 
 impl Flare{
-    pub fn new(size: Point, start_angle: f64, velocity: f64) -> Flare{
+    pub fn new(size: Point, start_angle: f64, velocity: f64, id: u64) -> Flare{
         let part = Part{radial: Point{x: -size.x, y: 0.0},
                         angle: Point{x: start_angle, y: start_angle + size.y},
                         color: [0.8, 0.3, 0.0, 1.0]};
         Flare{position: Point{x: 0.0,
                               y: 0.0},
               part: part,
-              velocity: velocity}
+              velocity: velocity,
+              id: id,
+              grazed: false}
+    }
+
+    pub fn get_id(&self) -> u64{
+        self.id
+    }
+
+    pub fn is_grazed(&self) -> bool{
+        self.grazed
+    }
+
+    pub fn set_grazed(&mut self){
+        self.grazed = true;
     }
 }
 
@@ -55,3 +80,25 @@ impl Enemy for Flare{
                                 y: current_position.y});
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::object::parts_collide;
+
+    fn player_at(radial: f64) -> Player {
+        Player::new(Point{x: radial, y: 0.5}, Point{x: 0.01, y: 0.01})
+    }
+
+    #[test]
+    fn only_the_front_of_a_flare_hits() {
+        let mut flare = Flare::new(Point{x: 0.4, y: 0.04}, 0.49, 0.0, 0);
+        flare.set_position(Point{x: 2.0, y: 0.0});
+        let hits = |player: Player| parts_collide(&flare.get_collision_parts(), &player.get_collision_parts());
+        assert!(hits(player_at(1.95)));
+        assert!(hits(player_at(1.75)));
+        assert!(!hits(player_at(1.62)));
+        // Still drawn there, so the player can see the tail they are passing through
+        assert!(parts_collide(&flare.get_render_parts(), &player_at(1.62).get_collision_parts()));
+    }
+}
@@ -0,0 +1,84 @@
+/*
+Defines the Available Game Modes and Their Rules
+*/
+
+use time;
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GameMode {
+    Endless,
+    TimeAttack { duration: f64 },
+    Zen,
+    DailyChallenge { date: u32 },
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Endless
+    }
+}
+
+impl GameMode {
+    pub fn daily_challenge() -> GameMode {
+        let today = time::now_utc();
+        let date = (today.tm_year + 1900) * 10000 + (today.tm_mon + 1) * 100 + today.tm_mday;
+        GameMode::DailyChallenge { date: date as u32 }
+    }
+
+    pub fn get_seed(&self) -> Option<u64> {
+        match *self {
+            GameMode::DailyChallenge { date } => Some(date as u64),
+            _ => None,
+        }
+    }
+
+    pub fn has_death(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    pub fn get_time_limit(&self) -> Option<f64> {
+        match *self {
+            GameMode::TimeAttack { duration } => Some(duration),
+            _ => None,
+        }
+    }
+
+    // Endless and daily runs score survival time; time attack and zen score grazes instead
+    pub fn scores_survival(&self) -> bool {
+        match *self {
+            GameMode::Endless | GameMode::DailyChallenge { .. } => true,
+            GameMode::TimeAttack { .. } | GameMode::Zen => false,
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        match *self {
+            GameMode::Endless => "Endless".to_string(),
+            GameMode::TimeAttack { duration } => format!("Time Attack {}s", duration),
+            GameMode::Zen => "Zen".to_string(),
+            GameMode::DailyChallenge { date } => format!("Daily {}", date),
+        }
+    }
+
+    // Each mode keeps its own leaderboard bucket; endless keeps the original file.
+    // Durations are named in whole milliseconds so that float formatting never splits a bucket
+    pub fn get_score_path(&self) -> String {
+        match *self {
+            GameMode::Endless => "highscore.txt".to_string(),
+            GameMode::TimeAttack { duration } => format!("highscore_time_attack_{}ms.txt", (duration * 1000.0).round() as u64),
+            GameMode::Zen => "highscore_zen.txt".to_string(),
+            GameMode::DailyChallenge { date } => format!("highscore_daily_{}.txt", date),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_attack_score_paths_use_whole_milliseconds() {
+        assert_eq!(GameMode::TimeAttack { duration: 60.0 }.get_score_path(), "highscore_time_attack_60000ms.txt");
+        assert_eq!(GameMode::TimeAttack { duration: 0.1 + 0.2 }.get_score_path(), "highscore_time_attack_300ms.txt");
+    }
+}
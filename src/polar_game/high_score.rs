@@ -1,40 +1,73 @@
 use ::rendering::renderables::text;
+use ::settings::warn;
+use super::game_mode::GameMode;
 use gg::rendering::{TextAlign, PlainText};
 use na::{Vector2, Vector3, Vector4, Rotation2};
 use std::fs::File;
 use std::string::String;
 use std::io::{Read, Write};
 
+/*
+Scores accrue in scaled simulation time, so slow motion never inflates a run.
+Runs that are not ranked (e.g. a non-standard game speed) still show a score
+but never touch the record.
+*/
 pub struct HighScore {
     current_score: f64,
-    record: f64
+    record: f64,
+    ranked: bool,
+    mode: GameMode
 }
 
 impl HighScore {
-    pub fn new() -> HighScore {
+    pub fn new(ranked: bool, mode: GameMode) -> HighScore {
         HighScore {
             current_score: 0.0,
-            record: HighScore::load_high_score()
+            record: HighScore::load_high_score(&mode.get_score_path()),
+            ranked: ranked,
+            mode: mode
         }
     }
 
     pub fn update(&mut self, t_step: f64) {
         self.current_score += t_step;
-        if self.current_score > self.record {
+        if self.ranked && self.current_score > self.record {
             self.record = self.current_score;
         }
     }
 
+    pub fn get_score(&self) -> f64 {
+        self.current_score
+    }
+
+    pub fn set_score(&mut self, score: f64) {
+        self.current_score = score;
+        if self.ranked && self.current_score > self.record {
+            self.record = self.current_score;
+        }
+    }
+
+    pub fn set_ranked(&mut self, ranked: bool) {
+        self.ranked = ranked;
+    }
+
     pub fn reset(&mut self) {
         self.current_score = 0.0;
     }
 
     fn get_score_string(&self) -> String {
-        format!("Current Score: {0:.2}", self.current_score)
+        if self.ranked {
+            format!("Current Score: {0:.2}", self.current_score)
+        } else {
+            format!("Current Score: {0:.2} (Unranked)", self.current_score)
+        }
     }
 
     fn get_record_string(&self) -> String {
-        format!("Record: {0:.2}", self.record)
+        match self.mode {
+            GameMode::Endless => format!("Record: {0:.2}", self.record),
+            _ => format!("Record ({0}): {1:.2}", self.mode.get_name(), self.record)
+        }
     }
 
     pub fn get_score_text(&self) -> PlainText {
@@ -61,34 +94,62 @@ impl HighScore {
         }
     }
 
-    pub fn load_high_score() -> f64 {
-        let path = "highscore.txt".to_string();
+    pub fn get_game_over_text(&self, title: &str, restart_key: &str) -> PlainText {
+        PlainText {
+            content: format!("{0} - Final Score: {1:.2} - Press {2} to Restart", title, self.current_score, restart_key),
+            position: Vector3::new(0.0, 0.0, 0.0),
+            scale: Vector2::new(0.1, 0.1),
+            transform: *Rotation2::new(0.0).matrix(),
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            fixed: true,
+            align: TextAlign::Center
+        }
+    }
+
+    pub fn get_run_code_text(&self, code: &str) -> PlainText {
+        PlainText {
+            content: format!("Run Code: {}", code),
+            position: Vector3::new(0.0, -0.1, 0.0),
+            scale: Vector2::new(0.06, 0.06),
+            transform: *Rotation2::new(0.0).matrix(),
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            fixed: true,
+            align: TextAlign::Center
+        }
+    }
+
+    pub fn load_high_score(path: &str) -> f64 {
         let mut file = match File::open(path){
             Ok(f) => f,
             Err(_) => return 0.0,
         };
         let mut score_string = String::new();
-        match file.read_to_string(&mut score_string){
-            Ok(r) => r,
-            Err(_) => panic!("failed to read score to string"),
-        };
-        match score_string.parse::<f64>(){
+        if let Err(e) = file.read_to_string(&mut score_string){
+            warn(&format!("Failed to read high score file {}: {}", path, e));
+            return 0.0;
+        }
+        match score_string.trim().parse::<f64>(){
             Ok(s) => s,
-            Err(_) => panic!("failed to parse string to f64"),
+            Err(e) => {
+                warn(&format!("Failed to parse high score file {}: {}", path, e));
+                0.0
+            }
         }
     }
 
     pub fn update_high_score(&self) {
-        if self.current_score < self.record { return; } 
+        if !self.ranked || self.current_score < self.record { return; } 
         let score_string = self.current_score.to_string();
-        let path = "highscore.txt".to_string();
-        let mut file = match File::create(path){
-            Ok(f) => f,
-            Err(_) => panic!("Failed to open High Score!"),
-        };
-        match file.write_all(score_string.as_bytes()){
+        let path = self.mode.get_score_path();
+        let mut file = match File::create(&path){
             Ok(f) => f,
-            Err(_) => panic!("failed to write sting to file"),
+            Err(e) => {
+                warn(&format!("Failed to create high score file {}: {}", path, e));
+                return;
+            }
         };
+        if let Err(e) = file.write_all(score_string.as_bytes()){
+            warn(&format!("Failed to write high score file {}: {}", path, e));
+        }
     }
 }
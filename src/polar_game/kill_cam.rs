@@ -0,0 +1,185 @@
+/*
+Keeps a Rolling Buffer of Recent States and Replays Them When the Player Dies
+*/
+
+use super::player::Player;
+use super::flare::Flare;
+use super::sun::Sun;
+use std::collections::VecDeque;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KillCamFrame {
+    pub player: Player,
+    pub flares: Vec<Flare>,
+    pub sun: Sun,
+    pub time: f64,
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum KillCause {
+    Flare(u64),
+    Sun,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KillCam {
+    frames: VecDeque<KillCamFrame>,
+    buffer_length: f64,
+    playback_speed: f64,
+    cursor: Option<f64>,
+    countdown: Option<f64>,
+    cause: Option<KillCause>,
+}
+
+impl KillCam {
+    pub fn new(buffer_length: f64, playback_speed: f64) -> KillCam {
+        KillCam {
+            frames: VecDeque::new(),
+            buffer_length: buffer_length,
+            playback_speed: playback_speed,
+            cursor: None,
+            countdown: None,
+            cause: None,
+        }
+    }
+
+    pub fn record(&mut self, frame: KillCamFrame) {
+        let cutoff = frame.time - self.buffer_length;
+        self.frames.push_back(frame);
+        while self.frames.front().map_or(false, |f| f.time < cutoff) {
+            self.frames.pop_front();
+        }
+    }
+
+    // Playback begins once the given real time has passed, so the game can play out the death first
+    pub fn start(&mut self, cause: KillCause, delay: f64) {
+        self.cause = Some(cause);
+        self.countdown = Some(delay);
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.cursor = None;
+        self.countdown = None;
+        self.cause = None;
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.countdown.is_some()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    // Counts down to playback, then advances it by real time until the final recorded frame is reached
+    pub fn update(&mut self, real_step: f64) {
+        if let Some(countdown) = self.countdown {
+            let remaining = countdown - real_step;
+            if remaining > 0.0 {
+                self.countdown = Some(remaining);
+            }
+            else {
+                self.countdown = None;
+                self.cursor = self.frames.front().map(|f| f.time);
+            }
+            return;
+        }
+        let end_time = match self.frames.back() {
+            Some(f) => f.time,
+            None => { self.cursor = None; return; }
+        };
+        if let Some(cursor) = self.cursor {
+            let next = cursor + real_step * self.playback_speed;
+            self.cursor = if next > end_time { None } else { Some(next) };
+        }
+    }
+
+    pub fn get_current_frame(&self) -> Option<&KillCamFrame> {
+        let cursor = match self.cursor {
+            Some(c) => c,
+            None => return None,
+        };
+        self.frames.iter().take_while(|f| f.time <= cursor).last()
+            .or(self.frames.front())
+    }
+
+    pub fn get_cause(&self) -> Option<KillCause> {
+        self.cause
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::object::Point;
+
+    fn frame(time: f64) -> KillCamFrame {
+        KillCamFrame {
+            player: Player::new(Point{x: 1.0, y: 0.0}, Point{x: 0.01, y: 0.01}),
+            flares: Vec::new(),
+            sun: Sun::new(0.1),
+            time: time
+        }
+    }
+
+    fn recorded(buffer_length: f64, playback_speed: f64, times: &[f64]) -> KillCam {
+        let mut kill_cam = KillCam::new(buffer_length, playback_speed);
+        for &t in times {
+            kill_cam.record(frame(t));
+        }
+        kill_cam
+    }
+
+    fn current_time(kill_cam: &KillCam) -> Option<f64> {
+        kill_cam.get_current_frame().map(|f| f.time)
+    }
+
+    #[test]
+    fn drops_frames_older_than_the_buffer() {
+        let mut kill_cam = recorded(1.0, 1.0, &[0.0, 0.5, 1.0, 1.5, 2.0]);
+        kill_cam.start(KillCause::Sun, 0.0);
+        kill_cam.update(0.0);
+        assert_eq!(current_time(&kill_cam), Some(1.0));
+    }
+
+    #[test]
+    fn waits_for_the_delay_before_playing() {
+        let mut kill_cam = recorded(3.0, 1.0, &[0.0, 0.5]);
+        kill_cam.start(KillCause::Flare(3), 0.5);
+        assert!(kill_cam.is_pending() && !kill_cam.is_playing());
+        kill_cam.update(0.3);
+        assert!(kill_cam.is_pending() && !kill_cam.is_playing());
+        kill_cam.update(0.3);
+        assert!(!kill_cam.is_pending() && kill_cam.is_playing());
+        assert_eq!(current_time(&kill_cam), Some(0.0));
+        assert!(kill_cam.get_cause() == Some(KillCause::Flare(3)));
+    }
+
+    #[test]
+    fn plays_back_at_its_speed_and_stops_after_the_last_frame() {
+        let mut kill_cam = recorded(3.0, 0.5, &[0.0, 0.5, 1.0]);
+        kill_cam.start(KillCause::Sun, 0.0);
+        kill_cam.update(0.0);
+        kill_cam.update(1.0);
+        assert_eq!(current_time(&kill_cam), Some(0.5));
+        kill_cam.update(0.9);
+        assert_eq!(current_time(&kill_cam), Some(0.5));
+        kill_cam.update(0.2);
+        assert!(!kill_cam.is_playing());
+        assert_eq!(current_time(&kill_cam), None);
+    }
+
+    #[test]
+    fn clear_stops_playback_and_forgets_frames() {
+        let mut kill_cam = recorded(3.0, 1.0, &[0.0, 0.5]);
+        kill_cam.start(KillCause::Sun, 0.0);
+        kill_cam.update(0.0);
+        kill_cam.clear();
+        assert!(!kill_cam.is_playing() && !kill_cam.is_pending());
+        assert!(kill_cam.get_cause() == None);
+        kill_cam.start(KillCause::Sun, 0.0);
+        kill_cam.update(0.0);
+        assert!(!kill_cam.is_playing());
+    }
+}
@@ -2,35 +2,88 @@
 Central file for the polar_game module
 */
 
-mod player;
+pub mod player;
 pub mod object;
 mod enemy;
-mod flare;
-mod sun;
-mod frame;
+pub mod flare;
+pub mod sun;
+pub mod frame;
 mod high_score;
+mod time_scale;
+pub mod game_mode;
+mod kill_cam;
+mod rng;
+mod rewind;
+pub mod run_code;
+pub mod snapshot;
+mod settings_menu;
+pub mod replay;
+pub mod controls;
+pub mod analogue;
+pub mod mouse;
+mod view_mode;
+pub mod camera;
+mod photo;
+pub mod theme;
+pub mod trail;
+pub mod particles;
+pub mod screenshot;
 pub mod builder;
 pub use self::builder::PolarGameBuilder;
+pub use self::game_mode::GameMode;
 
 use self::player::Player;
-use self::object::{Part,Object,Point,collision};
+use self::object::{Part,Object,Point,forgiving_collision,parts_collide};
 use self::flare::Flare;
 use self::sun::Sun;
 use self::enemy::Enemy;
 use self::frame::PolarFrame;
 use self::high_score::HighScore;
-use time;
+use self::time_scale::TimeScale;
+use self::kill_cam::{KillCam, KillCamFrame, KillCause};
+use self::rng::GameRng;
+use self::rewind::Rewind;
+use self::settings_menu::SettingsMenu;
+use self::replay::{Replay, ReplayPlayback};
+use self::controls::Action;
+use self::mouse::{MouseControl, MouseCursor};
+use self::view_mode::ViewMode;
+use self::camera::Camera;
+use self::photo::{PhotoMode, PhotoInput};
+use self::theme::Theme;
+use self::trail::Trails;
+use self::screenshot::CaptureWorker;
+use self::particles::{ParticleSystem, EffectLibrary, EffectKind};
+use self::controls::rising_edge;
+use ::settings::{Settings, warn};
+use self::snapshot::{GameSnapshot, SNAPSHOT_VERSION, SUSPEND_PATH};
 use rand;
+use rand::Rng;
 use rand::distributions::exponential::Exp;
 use rand::distributions::IndependentSample;
 use rand::distributions::range::Range;
 use gg::games::{GameInput, Game};
 use gg::input::keyboard::KeyboardInput;
 use gg::input::joystick::JoystickInput;
+use gg::input::mouse::MouseInput;
 use gg::games::view_details::{PolarViewDetails, ViewDetails};
-use gg::rendering::{WindowSpec};
+use gg::rendering::{WindowSpec, PlainText};
 use gg::debug::*;
-use ::rendering::{PolarPixel, PolarPrimitive, PolarRenderable};
+use ::rendering::{PolarPixel, PixelFill, StaticPixels, PolarPixelBatch, UnrolledPixel, Background, PolarPrimitive, PolarRenderable};
+use ::rendering::post_process::PostEffects;
+use na::Vector4;
+use std::rc::Rc;
+
+const DEATH_CAM_SCALE: f64 = 0.2;
+const DEATH_CAM_RAMP: f64 = 0.5;
+const KILL_CAM_LENGTH: f64 = 3.0;
+const KILL_CAM_SPEED: f64 = 0.25;
+const REWIND_INTERVAL: f64 = 0.05;
+const GRAZE_MARGIN: Point = Point{x: 0.05, y: 0.01};
+const GRAZE_POINTS: f64 = 1.0;
+const ABERRATION_DURATION: f64 = 1.5;
+// Aberration left on screen for as long as the player stays dead
+const ABERRATION_REST: f64 = 0.15;
 
 pub struct PolarGame{
     player: Player,
@@ -38,67 +91,619 @@ pub struct PolarGame{
     sun: Sun,
     pub input_keys: InputKeys,
     frame: PolarFrame,
+    frame_pixels: StaticPixels,
     pub setup: GameSetup,
     time: Times,
+    time_scale: TimeScale,
     pub state: GameState,
     external_input: ExternalInput,
     view_details: PolarViewDetails,
-    high_score: HighScore
+    high_score: HighScore,
+    kill_cam: KillCam,
+    next_flare_id: u64,
+    rng: GameRng,
+    run_seed: u64,
+    rewind: Rewind,
+    settings_menu: SettingsMenu,
+    recording: Option<(String, Replay)>,
+    playback: Option<ReplayPlayback>,
+    replay_step: Option<f64>,
+    mouse_control: MouseControl,
+    aspect_ratio: f64,
+    view_mode: ViewMode,
+    camera: Camera,
+    photo_mode: PhotoMode,
+    theme: Theme,
+    theme_lock: bool,
+    suspend_lock: bool,
+    aberration: f64,
+    trails: Trails,
+    particles: ParticleSystem,
+    // Set once a snapshot or replay is loaded, since their contents cannot be trusted for the high score table
+    untrusted: bool,
+    capture_worker: Option<CaptureWorker>
 }
 
 impl PolarGame {
-    pub fn new(setup: GameSetup) -> PolarGame{
+    pub fn new(setup: GameSetup, settings: Settings, settings_path: String) -> PolarGame{
+        let run_seed = PolarGame::choose_seed(setup);
+        let mut rng = GameRng::from_seed(run_seed);
+        let time = Times::new(0.0, &mut rng);
+        let frame = PolarFrame::new(0.5, 0.05, Point{x: 0.01, y: 0.02}, setup.radial_max);
+        let trails = Trails::new(settings.trails);
+        let particles = ParticleSystem::new(EffectLibrary::load(&settings.appearance.effects_path), run_seed);
+        let theme = Theme::find(&settings.appearance.theme, &settings.appearance.theme_directory).unwrap_or_default();
         PolarGame{
             player: Player::new(setup.player_start, setup.player_width),
             flares: Vec::new(),
             sun: Sun::new(1.0),
             input_keys: InputKeys::default(),
-            time: Times::new(0.0),
-            frame: PolarFrame::new(0.5, 0.05, Point{x: 0.01, y: 0.02}, setup.radial_max),
+            time: time,
+            time_scale: TimeScale::new(setup.game_speed),
+            frame_pixels: PolarGame::build_frame_pixels(&frame, &theme, 0),
+            frame: frame,
             setup: setup,
             state: GameState::new(),
             external_input: Default::default(),
             view_details:  Default::default(),
-            high_score: HighScore::new()
+            high_score: HighScore::new(setup.is_ranked(), setup.mode),
+            kill_cam: KillCam::new(KILL_CAM_LENGTH, KILL_CAM_SPEED),
+            next_flare_id: 0,
+            rng: rng,
+            run_seed: run_seed,
+            rewind: Rewind::new(setup.rewind_length, REWIND_INTERVAL),
+            camera: Camera::new(settings.camera, setup.player_start.x),
+            settings_menu: SettingsMenu::new(settings, settings_path),
+            recording: None,
+            playback: None,
+            replay_step: None,
+            mouse_control: MouseControl::new(),
+            aspect_ratio: 16.0 / 9.0,
+            view_mode: ViewMode::new(setup.tunnel_mode),
+            photo_mode: PhotoMode::new(),
+            theme: theme,
+            theme_lock: false,
+            suspend_lock: false,
+            aberration: 0.0,
+            trails: trails,
+            particles: particles,
+            untrusted: false,
+            capture_worker: None
+        }
+    }
+
+    pub fn record_to(&mut self, path: &str) {
+        self.recording = Some((path.to_string(), Replay::new(self.setup)));
+    }
+
+    pub fn play_replay(&mut self, replay: Replay) {
+        self.untrusted = true;
+        self.high_score.set_ranked(false);
+        self.playback = Some(ReplayPlayback::new(replay));
+    }
+
+    pub fn is_replay_finished(&self) -> bool {
+        self.playback.as_ref().map_or(true, |p| p.is_finished())
+    }
+
+    // The step the next update_logic will take from the replay, whatever step it is given
+    pub fn get_replay_step(&self) -> Option<f64> {
+        self.replay_step
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state.player_death || self.state.run_complete
+    }
+
+    pub fn get_score(&self) -> f64 {
+        self.high_score.get_score()
+    }
+
+    // Returns true while a replay is supplying the inputs
+    fn update_replay_input(&mut self) -> bool {
+        let frame = match self.playback {
+            Some(ref mut playback) => playback.next_frame(),
+            None => return false
+        };
+        match frame {
+            Some(frame) => {
+                self.input_keys.jump_angle = frame.jump_angle;
+                self.input_keys.jump_radial = frame.jump_radial;
+                self.input_keys.reset = frame.reset;
+                self.input_keys.rewind = frame.rewind;
+                self.input_keys.pause = frame.pause;
+                self.replay_step = Some(frame.t_step);
+                if let Some(setup) = frame.setup {
+                    self.apply_setup(setup);
+                }
+                true
+            },
+            None => {
+                self.playback = None;
+                self.input_keys = InputKeys::default();
+                false
+            }
+        }
+    }
+
+    fn apply_settings(&mut self) {
+        let gameplay = self.settings_menu.settings.gameplay;
+        let mut setup = self.setup;
+        setup.game_speed = gameplay.game_speed;
+        setup.hitbox_forgiveness = gameplay.hitbox_forgiveness;
+        setup.show_hitboxes = gameplay.show_hitboxes;
+        setup.tunnel_mode = gameplay.tunnel_mode;
+        setup.practice_mode = gameplay.practice_mode;
+        if let Some((_, ref mut replay)) = self.recording {
+            replay.record_setup(setup);
+        }
+        self.apply_setup(setup);
+        self.camera.settings = self.settings_menu.settings.camera;
+        self.trails.settings = self.settings_menu.settings.trails;
+        let appearance = self.settings_menu.settings.appearance.clone();
+        if appearance.theme != self.theme.name {
+            self.set_theme(&appearance.theme);
+        }
+    }
+
+    // Gameplay changes restart the run so that a changed game speed, hitbox or rewind cannot leak into a ranked score.
+    // Replays call this too, so that they restart at the same points as the recorded run
+    fn apply_setup(&mut self, setup: GameSetup) {
+        let restart = setup.game_speed != self.setup.game_speed
+            || setup.hitbox_forgiveness != self.setup.hitbox_forgiveness
+            || setup.practice_mode != self.setup.practice_mode;
+        self.setup = setup;
+        self.view_mode.set_tunnel(setup.tunnel_mode);
+        if restart {
+            self.high_score.set_ranked(self.setup.is_ranked() && !self.untrusted);
+            self.reset();
+        }
+    }
+
+    // Unseeded setups get a fresh seed for every run so that each run can still be shared
+    fn choose_seed(setup: GameSetup) -> u64 {
+        match setup.seed {
+            Some(seed) => seed,
+            None => rand::thread_rng().gen()
+        }
+    }
+
+    pub fn get_run_code(&self) -> String {
+        run_code::encode(&self.setup, self.run_seed)
+    }
+
+    pub fn snapshot(&self) -> GameSnapshot {
+        self.build_snapshot(self.kill_cam.clone())
+    }
+
+    fn build_snapshot(&self, kill_cam: KillCam) -> GameSnapshot {
+        GameSnapshot {
+            version: SNAPSHOT_VERSION,
+            setup: self.setup,
+            player: self.player.clone(),
+            flares: self.flares.clone(),
+            sun: self.sun,
+            time: self.time,
+            time_scale: self.time_scale,
+            state: self.state,
+            kill_cam: kill_cam,
+            score: self.high_score.get_score(),
+            next_flare_id: self.next_flare_id,
+            rng: self.rng,
+            run_seed: self.run_seed
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: GameSnapshot) {
+        self.untrusted = true;
+        self.apply_snapshot(snapshot);
+    }
+
+    fn apply_snapshot(&mut self, snapshot: GameSnapshot) {
+        if snapshot.setup.radial_max != self.setup.radial_max {
+            self.frame = PolarFrame::new(0.5, 0.05, Point{x: 0.01, y: 0.02}, snapshot.setup.radial_max);
+            self.frame_pixels = PolarGame::build_frame_pixels(&self.frame, &self.theme, self.frame_pixels.key + 1);
+        }
+        self.setup = snapshot.setup;
+        self.player = snapshot.player;
+        self.flares = snapshot.flares;
+        self.sun = snapshot.sun;
+        self.time = snapshot.time;
+        self.time_scale = snapshot.time_scale;
+        self.state = snapshot.state;
+        self.kill_cam = snapshot.kill_cam;
+        self.high_score.set_ranked(self.setup.is_ranked() && !self.untrusted);
+        self.high_score.set_score(snapshot.score);
+        self.next_flare_id = snapshot.next_flare_id;
+        self.rng = snapshot.rng;
+        self.run_seed = snapshot.run_seed;
+        self.camera.snap();
+        self.trails.clear();
+        self.particles.clear();
+        self.update_view_details(0.0);
+    }
+
+    // Saves the run for --resume and pauses it, so nothing is lost between the save and quitting
+    fn suspend(&mut self) {
+        match self.snapshot().save(SUSPEND_PATH) {
+            Ok(()) => {
+                debug(&format!("Suspended the run to {}", SUSPEND_PATH));
+                self.input_keys.pause = true;
+            },
+            Err(e) => warn(&format!("Failed to suspend the run to {}: {}", SUSPEND_PATH, e))
         }
     }
 
     pub fn reset(&mut self) {
         self.player = Player::new(self.setup.player_start, self.setup.player_width);
         self.high_score.reset();
-        self.time = Times::new(0.0);
+        self.run_seed = PolarGame::choose_seed(self.setup);
+        self.rng = GameRng::from_seed(self.run_seed);
+        self.time = Times::new(0.0, &mut self.rng);
+        self.time_scale = TimeScale::new(self.setup.game_speed);
         self.flares = Vec::new();
+        self.next_flare_id = 0;
+        self.state = GameState::new();
+        self.kill_cam.clear();
+        self.rewind.clear();
+        self.mouse_control.reset();
+        self.camera.reset();
+        self.aberration = 0.0;
+        self.trails.clear();
+        self.particles.reset(self.run_seed);
+    }
+
+    // Rewind snapshots leave out the kill cam buffer, which is rebuilt as play resumes
+    fn record_rewind_snapshot(&mut self, t_step: f64) {
+        if self.rewind.should_record(t_step) {
+            let snapshot = self.build_snapshot(KillCam::new(KILL_CAM_LENGTH, KILL_CAM_SPEED));
+            self.rewind.record(snapshot);
+        }
+    }
+
+    fn update_rewind(&mut self, t_step: f64) {
+        if let Some(snapshot) = self.rewind.step_back(t_step) {
+            self.apply_snapshot(snapshot);
+        }
+    }
+
+    fn update_view_details(&mut self, real_step: f64) {
+        self.view_details.length_circle = self.setup.length_circle;
+        self.camera.update(&mut self.view_details, &self.player, real_step);
+        self.view_details.tunnel_mode = self.view_mode.is_tunnel();
+    }
+
+    fn get_camera(&self) -> PolarViewDetails {
+        if self.photo_mode.is_active() {
+            self.photo_mode.get_view()
+        }
+        else {
+            self.view_details.clone()
+        }
+    }
+
+    pub fn get_polar_view(&self) -> PolarViewDetails {
+        self.view_mode.get_view(&self.get_camera())
+    }
+
+    // The worker starts with the first capture, so runs without one never spawn its thread
+    fn save_screenshot(&mut self) {
+        let renderables = self.build_renderables();
+        let view = self.get_polar_view();
+        let settings = &self.settings_menu.settings;
+        let worker = self.capture_worker.get_or_insert_with(|| CaptureWorker::new(&settings.capture, &settings.appearance.textures));
+        if let Err(e) = worker.request(renderables, view, settings.display.width, settings.display.height) {
+            debug(&format!("Failed to save screenshot: {}", e));
+        }
+    }
+
+    fn log_screenshots(results: Vec<Result<String, String>>) {
+        for result in results {
+            match result {
+                Ok(path) => debug(&format!("Saved screenshot to {}", path)),
+                Err(e) => debug(&format!("Failed to save screenshot: {}", e))
+            }
+        }
+    }
+
+    // Photo mode and captures sit outside the simulation, so they also work while a replay plays
+    fn update_capture_input(&mut self) {
+        let camera = self.get_camera();
+        let controls = &self.settings_menu.settings.controls;
+        let input = &self.external_input;
+        self.photo_mode.update_toggle(controls.is_pressed(Action::PhotoMode, input), &camera);
+        self.photo_mode.update_capture(controls.is_pressed(Action::Screenshot, input));
+        if self.photo_mode.is_active() {
+            let stick = self.settings_menu.settings.input.stick.map(input.gamepad.get_x_axis(), input.gamepad.get_y_axis());
+            let axis_input = |positive: Action, negative: Action| analogue::blend(
+                controls.get_digital(positive, input) - controls.get_digital(negative, input),
+                controls.get_analogue(positive, stick) - controls.get_analogue(negative, stick));
+            self.photo_mode.set_input(PhotoInput {
+                rotate: axis_input(Action::MoveRight, Action::MoveLeft),
+                radial: axis_input(Action::MoveUp, Action::MoveDown),
+                zoom: controls.get_digital(Action::ZoomIn, input) - controls.get_digital(Action::ZoomOut, input)
+            });
+        }
+    }
+
+    // The view toggles only change the projection, so they stay available during replays
+    fn update_view_toggle(&mut self) {
+        let controls = &self.settings_menu.settings.controls;
+        self.view_mode.update_unrolled_toggle(controls.is_pressed(Action::ToggleUnrolled, &self.external_input));
+        let pressed = controls.is_pressed(Action::ToggleView, &self.external_input);
+        if self.view_mode.update_toggle(pressed) {
+            let tunnel = self.view_mode.is_tunnel();
+            self.setup.tunnel_mode = tunnel;
+            self.settings_menu.settings.gameplay.tunnel_mode = tunnel;
+            self.view_details.tunnel_mode = tunnel;
+        }
+    }
+
+    // Unknown themes leave the current one in place
+    fn set_theme(&mut self, name: &str) {
+        let directory = self.settings_menu.settings.appearance.theme_directory.clone();
+        match Theme::find(name, &directory) {
+            Ok(theme) => {
+                self.frame_pixels = PolarGame::build_frame_pixels(&self.frame, &theme, self.frame_pixels.key + 1);
+                self.settings_menu.settings.appearance.theme = theme.name.clone();
+                self.theme = theme;
+            },
+            Err(e) => debug(&e)
+        }
+    }
+
+    fn update_theme_toggle(&mut self) {
+        let pressed = self.settings_menu.settings.controls.is_pressed(Action::CycleTheme, &self.external_input);
+        if rising_edge(pressed, &mut self.theme_lock) {
+            let next = theme::next_theme(&self.theme.name, &self.settings_menu.settings.appearance.theme_directory, 1);
+            self.set_theme(&next);
+        }
+    }
+
+    fn build_frame_pixels(frame: &PolarFrame, theme: &Theme, key: u64) -> StaticPixels {
+        let fill = theme.frame_texture.map_or(PixelFill::Flat, PixelFill::Textured);
+        StaticPixels {
+            key: key,
+            pixels: Rc::new(frame.get_render_parts().into_iter()
+                .map(|p| PolarPixel::from(p.with_color(theme.frame)).with_fill(fill))
+                .collect())
+        }
+    }
+
+    fn record_trails(&mut self, t_step: f64) {
+        if !self.trails.start_sample(t_step) {
+            return;
+        }
+        self.trails.record_player(self.player.get_collision_parts().first().cloned());
+        for f in self.flares.iter() {
+            self.trails.record_flare(f.get_id(), f.get_render_parts()[0]);
+        }
+        self.trails.end_sample();
+    }
+
+    fn get_post_effects(&self) -> PostEffects {
+        let aberration = if self.player.destroyed { self.aberration.max(ABERRATION_REST) } else { self.aberration };
+        PostEffects {
+            settings: self.settings_menu.settings.post_process,
+            aberration: aberration
+        }
+    }
+
+    fn themed_text(&self, mut text: PlainText) -> Box<PolarRenderable> {
+        let color = self.theme.text;
+        text.color = Vector4::new(color[0] as _, color[1] as _, color[2] as _, color[3] as _);
+        Box::new(text)
+    }
+
+    // Photo mode hides the HUD so that captures show only the playfield
+    pub fn build_renderables(&self) -> Vec<Box<PolarRenderable>> {
+        // The frame stays uploaded on the GPU unless the projection is being blended or unrolled
+        let static_frame = !self.view_mode.is_transitioning() && !self.view_mode.is_unrolled();
+        let theme = &self.theme;
+        let mut rend_vec: Vec<Part> = Vec::new();
+        // Flares are drawn with a gradient, so their place in the batch is kept
+        let mut flare_range = 0..0;
+        if !static_frame {
+            rend_vec.extend(self.frame.get_render_parts().into_iter().map(|p| p.with_color(theme.frame)));
+        }
+        if self.kill_cam.is_playing() {
+            rend_vec.extend(self.get_kill_cam_parts());
+        }
+        else {
+            // Trails go first so that the objects are drawn over them
+            self.trails.extend_flare_parts(&mut rend_vec, theme.flare);
+            self.trails.extend_player_parts(&mut rend_vec, theme.player);
+            for f in self.player.get_render_parts().into_iter(){
+                rend_vec.push(f.with_color(theme.player));
+            }
+            let sun_part = self.sun.get_render_parts()[0].with_color(theme.sun);
+            debug_clock_start("Render::get_renderables::flares");
+            let flares_start = rend_vec.len();
+            for f in self.flares.iter(){
+                let flare_part = f.get_render_parts()[0].with_color(theme.flare_tail);
+                rend_vec.push(flare_part);
+            }
+            flare_range = flares_start..rend_vec.len();
+            debug_clock_stop("Render::get_renderables::flares");
+            rend_vec.push(sun_part);
+            self.particles.extend_parts(&mut rend_vec);
+            if self.setup.show_hitboxes {
+                rend_vec.extend(self.get_hitbox_parts());
+            }
+        }
+        let rend_vec = self.view_mode.project_parts(rend_vec, &self.get_camera());
+        let mut output: Vec<Box<PolarRenderable>> = Vec::with_capacity(8);
+        output.push(Box::new(Background { color: theme.background }));
+        output.push(Box::new(self.get_post_effects()));
+        if self.view_mode.is_unrolled() {
+            // Unrolled pixels are flat, so flares keep their head colour
+            output.extend(rend_vec.into_iter().enumerate()
+                .map(|(i, p)| if flare_range.contains(&i) { p.with_color(theme.flare) } else { p })
+                .map(|p| -> Box<PolarRenderable> {Box::new(UnrolledPixel::from(PolarPixel::from(p)))}));
+        }
+        else {
+            if static_frame {
+                output.push(Box::new(self.frame_pixels.clone()));
+            }
+            // Flares move outwards, so they fade from the tail colour inside to the flare colour at the head
+            let pixels = rend_vec.into_iter().enumerate().map(|(i, p)| {
+                let pixel = PolarPixel::from(p);
+                if flare_range.contains(&i) { pixel.with_fill(PixelFill::RadialGradient(theme.flare)) } else { pixel }
+            }).collect();
+            output.push(Box::new(PolarPixelBatch { pixels: pixels }));
+        }
+        if self.photo_mode.is_active() {
+            return output;
+        }
+
+        let score_text = self.high_score.get_score_text();
+        let record_text = self.high_score.get_record_text();
+        output.push(self.themed_text(score_text));
+        output.push(self.themed_text(record_text));
+        let controls = &self.settings_menu.settings.controls;
+        let restart_key = controls.get_prompt(Action::Reset)
+            .or_else(|| controls.get_prompt(Action::Confirm))
+            .unwrap_or_else(|| "Reset".to_string());
+        if self.state.player_death {
+            output.push(self.themed_text(self.high_score.get_game_over_text("Game Over", &restart_key)));
+        }
+        else if self.state.run_complete {
+            output.push(self.themed_text(self.high_score.get_game_over_text("Time Up", &restart_key)));
+        }
+        if self.state.player_death || self.state.run_complete {
+            output.push(self.themed_text(self.high_score.get_run_code_text(&self.get_run_code())));
+        }
+        if self.settings_menu.is_open() {
+            for text in self.settings_menu.get_texts() {
+                output.push(self.themed_text(text));
+            }
+        }
+        output
+    }
+
+    fn record_kill_cam_frame(&mut self) {
+        self.kill_cam.record(KillCamFrame {
+            player: self.player.clone(),
+            flares: self.flares.clone(),
+            sun: self.sun,
+            time: self.time.elapsed
+        });
     }
 
-    fn update_view_details(&mut self) {
-        self.view_details.radial_shift = self.player.get_position().x - 0.75;
-        self.view_details.rotation_angle = self.player.get_position().y + 0.25 + self.player.get_width() / 2.0;
-        self.view_details.length_total = (self.player.get_position().x + 0.25).max(1.0);
+    fn update_kill_cam(&mut self, t_step: f64) {
+        self.kill_cam.update(t_step);
+        match self.kill_cam.get_current_frame() {
+            Some(frame) => self.camera.update(&mut self.view_details, &frame.player, t_step),
+            None => self.state.player_death = true
+        }
+    }
+
+    fn get_kill_cam_parts(&self) -> Vec<Part> {
+        let theme = &self.theme;
+        let frame = match self.kill_cam.get_current_frame() {
+            Some(f) => f,
+            None => return Vec::new()
+        };
+        let cause = self.kill_cam.get_cause();
+        let mut parts: Vec<Part> = frame.player.get_render_parts().into_iter().map(|p| p.with_color(theme.player)).collect();
+        for f in frame.flares.iter() {
+            let flare_part = f.get_render_parts()[0];
+            if cause == Some(KillCause::Flare(f.get_id())) {
+                parts.push(flare_part.with_color(theme.highlight));
+            } else {
+                parts.push(flare_part.with_color(theme.flare));
+            }
+        }
+        let sun_part = frame.sun.get_render_parts()[0];
+        if cause == Some(KillCause::Sun) {
+            parts.push(sun_part.with_color(theme.highlight));
+        } else {
+            parts.push(sun_part.with_color(theme.sun));
+        }
+        parts
+    }
+
+    fn is_graze(flare: &Flare, player: &Player) -> bool {
+        let near_parts: Vec<Part> = flare.get_collision_parts().into_iter()
+            .map(|p| p.expand(GRAZE_MARGIN)).collect();
+        parts_collide(&near_parts, &player.get_collision_parts())
+    }
+
+    fn get_hitbox_parts(&self) -> Vec<Part> {
+        let color = self.theme.hitbox;
+        let forgiveness = self.setup.hitbox_forgiveness;
+        let mut parts: Vec<Part> = Vec::new();
+        for f in self.flares.iter() {
+            parts.extend(f.get_collision_parts().into_iter().map(|p| p.shrink(forgiveness).with_color(color)));
+        }
+        parts.extend(self.sun.get_collision_parts().into_iter().map(|p| p.shrink(forgiveness).with_color(color)));
+        parts.extend(self.player.get_collision_parts().into_iter().map(|p| p.with_color(color)));
+        parts
     }
 }
 
 impl Game for PolarGame {
     type Primitive = PolarPrimitive;
 
-    fn init(&mut self) {
-        self.time = Times::new(time::precise_time_s());
-        self.high_score.reset();
-    }
+    // new() and restore() already leave the run ready to start, so drawing the timers again here would
+    // make the first run of a seed differ from its resets and replays
+    fn init(&mut self) {}
 
     fn update_input(&mut self) {
-        self.input_keys.jump_radial = (self.external_input.kbd.get_up() as isize - (self.external_input.kbd.get_down() as isize)) as f64 * 0.3;
-        
-        if self.external_input.gamepad.get_y_axis().abs() > 0.1 {
-            self.input_keys.jump_radial = self.external_input.gamepad.get_y_axis() * 0.3;
+        self.update_view_toggle();
+        self.update_theme_toggle();
+        self.update_capture_input();
+        if self.photo_mode.is_active() {
+            return;
+        }
+        if self.update_replay_input() {
+            return;
         }
-       
-        self.input_keys.jump_angle = (self.external_input.kbd.get_right() as isize - (self.external_input.kbd.get_left() as isize)) as f64 * 0.3;
 
-        if self.external_input.gamepad.get_x_axis().abs() > 0.1 {
-            self.input_keys.jump_angle = self.external_input.gamepad.get_x_axis() * 0.3;
+        let closed = {
+            let controls = &self.settings_menu.settings.controls;
+            let input = &self.external_input;
+            let toggle = controls.is_pressed(Action::Settings, input);
+            let up = controls.is_pressed(Action::MoveUp, input);
+            let down = controls.is_pressed(Action::MoveDown, input);
+            let left = controls.is_pressed(Action::MoveLeft, input);
+            let right = controls.is_pressed(Action::MoveRight, input);
+            self.settings_menu.update(toggle, up, down, left, right)
+        };
+        if closed {
+            self.apply_settings();
+        }
+        if self.settings_menu.is_open() {
+            self.input_keys.jump_radial = 0.0;
+            self.input_keys.jump_angle = 0.0;
+            return;
+        }
+
+        let speed = self.settings_menu.settings.input.movement_speed;
+        let controls = &self.settings_menu.settings.controls;
+        let input = &self.external_input;
+        let stick = self.settings_menu.settings.input.stick.map(input.gamepad.get_x_axis(), input.gamepad.get_y_axis());
+        let axis_input = |positive: Action, negative: Action| analogue::blend(
+            controls.get_digital(positive, input) - controls.get_digital(negative, input),
+            controls.get_analogue(positive, stick) - controls.get_analogue(negative, stick));
+        self.input_keys.jump_radial = axis_input(Action::MoveUp, Action::MoveDown) * speed;
+        self.input_keys.jump_angle = axis_input(Action::MoveRight, Action::MoveLeft) * speed;
+
+        let mouse_settings = self.settings_menu.settings.input.mouse;
+        if mouse_settings.enabled && !self.player.destroyed {
+            let cursor = MouseCursor {
+                position: input.mouse.get_position(),
+                wheel: input.mouse.get_wheel_delta(),
+                aspect_ratio: self.aspect_ratio,
+                unrolled: self.view_mode.is_unrolled()
+            };
+            let (jump_radial, jump_angle) = self.mouse_control.steer(&mouse_settings, cursor, &self.view_details,
+                                                                     self.player.get_center(), self.setup.radial_max, speed);
+            self.input_keys.jump_radial = jump_radial;
+            self.input_keys.jump_angle = jump_angle;
         }
         
-        match (self.external_input.kbd.get_p(), self.input_keys.pause, self.input_keys.pause_lock) {
+        match (controls.is_pressed(Action::Pause, input), self.input_keys.pause, self.input_keys.pause_lock) {
             (true, false, false) => { self.input_keys.pause = true; self.input_keys.pause_lock = true; },
             (false, true, true) => { self.input_keys.pause_lock = false; },
             (true, true, false) => { self.input_keys.pause = false; self.input_keys.pause_lock = true; },
@@ -106,91 +711,169 @@ impl Game for PolarGame {
             _ => () 
         };
         
-        self.input_keys.reset = self.external_input.kbd.get_r();
+        let run_over = self.state.player_death || self.state.run_complete;
+        self.input_keys.reset = controls.is_pressed(Action::Reset, input)
+            || (run_over && controls.is_pressed(Action::Confirm, input));
+        self.input_keys.rewind = controls.is_pressed(Action::Rewind, input);
+        if rising_edge(controls.is_pressed(Action::Suspend, input), &mut self.suspend_lock) {
+            self.suspend();
+        }
     }
 
     fn update_logic(&mut self, t_step: f64){
         debug_clock_start("Logic::update_logic");
+        self.view_mode.update(t_step);
+        self.aberration = (self.aberration - t_step / ABERRATION_DURATION).max(0.0);
+        if self.photo_mode.is_active() {
+            self.photo_mode.update(t_step);
+            return;
+        }
+        // Time spent in the settings menu is left out of recordings, since playback never opens it
+        if self.settings_menu.is_open() { return; }
+        let t_step = self.replay_step.take().unwrap_or(t_step);
+        if let Some((_, ref mut replay)) = self.recording {
+            replay.record(t_step, &self.input_keys);
+        }
 
         if self.input_keys.pause { return; }
         if self.input_keys.reset {
             self.reset();
             return;
         }
-        
+        if self.setup.practice_mode && self.input_keys.rewind {
+            self.update_rewind(t_step);
+            return;
+        }
+        if self.kill_cam.is_playing() {
+            self.update_kill_cam(t_step);
+            return;
+        }
+        if self.state.run_complete { return; }
+        if self.kill_cam.is_pending() {
+            self.kill_cam.update(t_step);
+        }
+
+        let real_step = t_step;
+        self.time_scale.update(t_step);
+        let t_step = self.time_scale.scale(t_step);
+
         let shift = Point{x: self.input_keys.jump_radial,
                           y: self.input_keys.jump_angle / 2.0};
         self.time.elapsed += t_step;
+        self.particles.update(t_step);
+        let was_destroyed = self.player.destroyed;
+        let mut cause: Option<KillCause> = None;
 
         self.player.update_position(shift, t_step, self.setup);
+        let has_death = self.setup.mode.has_death();
+        let mut grazes = 0;
+        let radial_max = self.setup.radial_max;
         for mut f in self.flares.iter_mut(){
+            let head = f.get_position().x;
             f.update_position(t_step, &self.player);
-            if collision(&*f, &self.player){
-                self.player.collide();;
+            // Flares leave the playfield here, long before they are despawned, so the burst is visible
+            if head < radial_max && f.get_position().x >= radial_max {
+                let part = f.get_render_parts()[0];
+                let edge = Point{x: radial_max, y: (part.angle.x + part.angle.y) / 2.0};
+                self.particles.emit(EffectKind::FlareDespawn, edge, self.theme.flare);
+            }
+            let hit = forgiving_collision(&*f, &self.player, self.setup.hitbox_forgiveness);
+            if hit && has_death {
+                self.player.collide();
+                cause = Some(KillCause::Flare(f.get_id()));
             }
+            else if !hit && !f.is_grazed() && PolarGame::is_graze(&*f, &self.player) {
+                f.set_grazed();
+                self.particles.emit(EffectKind::Graze, self.player.get_center(), self.theme.flare);
+                grazes += 1;
+            }
+        }
+        if has_death && forgiving_collision(&self.sun, &self.player, self.setup.hitbox_forgiveness){
+            self.player.collide();
+            cause = Some(KillCause::Sun);
         }
-        if collision(&self.sun, &self.player){
-            self.player.collide();;
+        if self.player.destroyed && !was_destroyed {
+            self.particles.emit(EffectKind::PlayerDeath, self.player.get_center(), self.theme.player);
+            self.camera.shake();
+            self.aberration = 1.0;
+            self.time_scale.ramp_to(DEATH_CAM_SCALE, DEATH_CAM_RAMP);
+            self.state.survival_time = self.time.elapsed - self.time.start;
+            // The fatal step itself, so that playback ends on the hit
+            self.record_kill_cam_frame();
+            // Playback waits for the slow motion to settle, with particles and trails still running
+            if let Some(cause) = cause {
+                self.kill_cam.start(cause, DEATH_CAM_RAMP);
+            }
+            if !self.kill_cam.is_pending() {
+                self.state.player_death = true;
+            }
         }
 
         let current_flares = self.flares.clone();
         let (_, flares_trimmed) : (Vec<Flare>, Vec<Flare>)
             = current_flares.into_iter().partition(|f| f.terminate_flag(Point{x: -1.0, y: self.setup.radial_max + 2.0}));
         self.flares = flares_trimmed;
+        self.record_trails(t_step);
+        if grazes > 0 {
+            self.camera.pulse();
+        }
 
 
         if self.time.elapsed - self.time.previous_flare > self.time.til_flare{
-            let mut rng = rand::thread_rng();
             let unif = Range::new(0.0, 1.0);
-            let sa = unif.ind_sample(&mut rng);
-            let r = unif.ind_sample(&mut rng) / 20.0 + 0.02;
-            let a = unif.ind_sample(&mut rng) / 50.0 + 0.005;
-            let v = unif.ind_sample(&mut rng) / 2.0 + 0.1;
-            let new_flare = Flare::new(Point{x: r, y: a}, sa, v);
+            let sa = unif.ind_sample(&mut self.rng);
+            let r = unif.ind_sample(&mut self.rng) / 20.0 + 0.02;
+            let a = unif.ind_sample(&mut self.rng) / 50.0 + 0.005;
+            let v = unif.ind_sample(&mut self.rng) * self.setup.difficulty.flare_speed_range
+                + self.setup.difficulty.flare_speed_min;
+            let new_flare = Flare::new(Point{x: r, y: a}, sa, v, self.next_flare_id);
+            self.next_flare_id += 1;
             self.flares.push(new_flare);
             self.time.previous_flare = self.time.elapsed;
-            let emit_average = 10.0 + self.time.elapsed - self.time.start;
+            let emit_average = self.setup.difficulty.spawn_rate_base
+                + self.setup.difficulty.spawn_rate_growth * (self.time.elapsed - self.time.start);
             let exp = Exp::new(emit_average);
-            self.time.til_flare = exp.ind_sample(&mut rng);
+            self.time.til_flare = exp.ind_sample(&mut self.rng);
         }
 
         if !self.player.destroyed {
-            self.high_score.update(t_step);
+            if self.setup.mode.scores_survival() {
+                self.high_score.update(t_step);
+            }
+            else {
+                self.high_score.update(grazes as f64 * GRAZE_POINTS);
+            }
+            self.record_kill_cam_frame();
+        }
+
+        if let Some(limit) = self.setup.mode.get_time_limit() {
+            if self.time.elapsed - self.time.start >= limit {
+                self.state.run_complete = true;
+            }
         }
         
-        self.update_view_details();
+        if self.setup.practice_mode {
+            self.record_rewind_snapshot(t_step);
+        }
+
+        self.update_view_details(real_step);
         debug_clock_stop("Logic::update_logic");
     }
 
     fn get_view(&self) -> ViewDetails {
-        ViewDetails::Polar(self.view_details.clone())
+        ViewDetails::Polar(self.get_polar_view())
     }
 
-    fn get_renderables(&mut self, _: WindowSpec) -> Vec<Box<PolarRenderable>> {
+    fn get_renderables(&mut self, window_spec: WindowSpec) -> Vec<Box<PolarRenderable>> {
         debug_clock_start("Render::get_renderables");
-        let mut rend_vec: Vec<Part> = Vec::new();
-        for f in self.frame.get_render_parts().into_iter(){
-            rend_vec.push(f);
-        }
-        for f in self.player.get_render_parts().into_iter(){
-            rend_vec.push(f);
+        self.aspect_ratio = window_spec.aspect_ratio;
+        if self.photo_mode.take_capture_request() {
+            self.save_screenshot();
         }
-        let sun_part = self.sun.get_render_parts()[0];
-        debug_clock_start("Render::get_renderables::flares");
-        for f in self.flares.iter(){
-            let flare_part = f.get_render_parts()[0];
-            rend_vec.push(flare_part);
+        if let Some(ref worker) = self.capture_worker {
+            PolarGame::log_screenshots(worker.take_results());
         }
-        debug_clock_stop("Render::get_renderables::flares");
-        rend_vec.push(sun_part);
-        let mut output: Vec<Box<PolarRenderable>> = rend_vec.into_iter()
-            .map(|p| -> Box<PolarRenderable> {Box::new(PolarPixel::from(p))}).collect();
-
-        let score_text = self.high_score.get_score_text();
-        let record_text = self.high_score.get_record_text();
-        output.push(Box::new(score_text));
-        output.push(Box::new(record_text));
-        
+        let output = self.build_renderables();
         debug_clock_stop("Render::get_renderables");
         output
     }
@@ -200,6 +883,14 @@ impl Game for PolarGame {
     }
 
     fn on_exit(&mut self) {
+        if let Some((ref path, ref replay)) = self.recording {
+            if let Err(e) = replay.save(path) {
+                debug(&format!("Failed to save replay: {}", e));
+            }
+        }
+        if let Some(worker) = self.capture_worker.take() {
+            PolarGame::log_screenshots(worker.finish());
+        }
         self.high_score.update_high_score();
     }
 }
@@ -209,16 +900,27 @@ pub struct InputKeys{
     pub jump_angle: f64,
     pub jump_radial: f64,
     pub reset: bool,
+    pub rewind: bool,
     pub pause: bool,
     pub pause_lock: bool
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct GameSetup{
     pub radial_max: f64,
     pub player_start: Point,
     pub player_width: Point,
-    pub tunnel_mode: bool
+    pub tunnel_mode: bool,
+    #[serde(default = "default_length_circle")]
+    pub length_circle: f64,
+    pub hitbox_forgiveness: f64,
+    pub show_hitboxes: bool,
+    pub game_speed: f64,
+    pub seed: Option<u64>,
+    pub practice_mode: bool,
+    pub rewind_length: f64,
+    pub mode: GameMode,
+    pub difficulty: Difficulty
 }
 
 impl Default for GameSetup {
@@ -227,26 +929,67 @@ impl Default for GameSetup {
             radial_max: 8.0,
             player_start: Point{x: 4.0, y: 0.75},
             player_width: Point{x: 0.02, y: 0.01},
-            tunnel_mode: true
+            tunnel_mode: true,
+            length_circle: default_length_circle(),
+            hitbox_forgiveness: 0.0,
+            show_hitboxes: false,
+            game_speed: 1.0,
+            seed: None,
+            practice_mode: false,
+            rewind_length: 5.0,
+            mode: GameMode::Endless,
+            difficulty: Difficulty::default()
+        }
+    }
+}
+
+fn default_length_circle() -> f64 {
+    1.0
+}
+
+impl GameSetup {
+    // Practice runs, non-standard game speeds and forgiving hitboxes never reach the high score table
+    pub fn is_ranked(&self) -> bool {
+        self.game_speed == 1.0 && self.hitbox_forgiveness == 0.0 && !self.practice_mode
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Difficulty{
+    pub spawn_rate_base: f64,
+    pub spawn_rate_growth: f64,
+    pub flare_speed_min: f64,
+    pub flare_speed_range: f64
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty {
+            spawn_rate_base: 10.0,
+            spawn_rate_growth: 1.0,
+            flare_speed_min: 0.1,
+            flare_speed_range: 0.5
         }
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct GameState{
     pub player_death: bool,
+    pub run_complete: bool,
     pub survival_time: f64,
 }
 
 impl GameState{
     pub fn new() -> GameState{
         GameState{ player_death: false,
+                   run_complete: false,
                    survival_time: 0.0,
         }
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct Times{
     til_flare: f64,
     previous_flare: f64,
@@ -255,10 +998,9 @@ pub struct Times{
 }
 
 impl Times{
-    pub fn new(start_time: f64) -> Times{
-        let mut rng = rand::thread_rng();
+    pub fn new(start_time: f64, rng: &mut GameRng) -> Times{
         let exp = Exp::new(1.0);
-        Times{ til_flare: exp.ind_sample(&mut rng),
+        Times{ til_flare: exp.ind_sample(rng),
                previous_flare: start_time,
                start: start_time,
                elapsed: start_time,
@@ -267,12 +1009,14 @@ impl Times{
 }
 
 #[derive(Clone, Default)]
-struct ExternalInput {
+pub struct ExternalInput {
     kbd: KeyboardInput,
-    gamepad: JoystickInput
+    gamepad: JoystickInput,
+    mouse: MouseInput
 }
 
 impl GameInput for ExternalInput {
     fn get_kbd_inp<'a>(&'a mut self) -> Option<&'a mut KeyboardInput> { Some(&mut self.kbd) }
     fn get_joystick_inp<'a>(&'a mut self) -> Option<&'a mut JoystickInput> { Some(&mut self.gamepad) }
+    fn get_mouse_inp<'a>(&'a mut self) -> Option<&'a mut MouseInput> { Some(&mut self.mouse) }
 }
@@ -0,0 +1,85 @@
+/*
+Steers the Player Towards a Target Set by the Mouse
+*/
+
+use super::object::Point;
+use ::rendering::projection::{screen_to_polar, unrolled_to_polar};
+use gg::games::view_details::PolarViewDetails;
+
+// Distances below these are treated as "arrived" and steering eases off
+const ANGLE_TOLERANCE: f64 = 0.01;
+const RADIAL_TOLERANCE: f64 = 0.05;
+const WHEEL_STEP: f64 = 0.25;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MouseRadial {
+    Distance,
+    Wheel,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MouseSettings {
+    pub enabled: bool,
+    pub radial: MouseRadial,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        MouseSettings {
+            enabled: false,
+            radial: MouseRadial::Distance,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MouseCursor {
+    pub position: (f64, f64),
+    pub wheel: f64,
+    pub aspect_ratio: f64,
+    pub unrolled: bool,
+}
+
+pub struct MouseControl {
+    wheel_target: Option<f64>,
+}
+
+impl MouseControl {
+    pub fn new() -> MouseControl {
+        MouseControl { wheel_target: None }
+    }
+
+    pub fn reset(&mut self) {
+        self.wheel_target = None;
+    }
+
+    // Returns (jump_radial, jump_angle) steering the player's centre towards the cursor
+    pub fn steer(&mut self, settings: &MouseSettings, cursor: MouseCursor, view: &PolarViewDetails,
+                 player_center: Point, radial_max: f64, speed: f64) -> (f64, f64) {
+        let (x, y) = cursor.position;
+        let (cursor_radial, cursor_angle) = if cursor.unrolled {
+            unrolled_to_polar(x, y, view)
+        } else {
+            screen_to_polar(x, y, cursor.aspect_ratio, view)
+        };
+
+        let radial_target = match settings.radial {
+            MouseRadial::Distance => cursor_radial,
+            MouseRadial::Wheel => {
+                let target = self.wheel_target.unwrap_or(player_center.x) + cursor.wheel * WHEEL_STEP;
+                let target = target.max(0.0).min(radial_max);
+                self.wheel_target = Some(target);
+                target
+            }
+        };
+
+        let mut angle_diff = cursor_angle - player_center.y;
+        angle_diff -= angle_diff.round();
+        let radial_diff = radial_target - player_center.x;
+
+        let jump_angle = (angle_diff / ANGLE_TOLERANCE).max(-1.0).min(1.0) * speed;
+        let jump_radial = (radial_diff / RADIAL_TOLERANCE).max(-1.0).min(1.0) * speed;
+        (jump_radial, jump_angle)
+    }
+}
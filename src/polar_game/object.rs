@@ -4,25 +4,27 @@ Handles Basic Polar Objects and Collision Detection
 
 use std::ops::*;
 use na::{Vector2, Vector4};
-use ::rendering::PolarPixel;
+use ::rendering::{PolarPixel, PixelFill};
 
 pub trait Object{
     fn set_position(&mut self, _: Point);
     fn get_position(&self) -> Point;
     fn get_render_parts(&self) -> Vec<Part>;
-    fn get_collision_parts(&self) -> Vec<Part>{
-        self.get_render_parts()
-    }
+    fn get_collision_parts(&self) -> Vec<Part>;
     fn terminate_flag(&self, boundary: Point) -> bool{
         return self.get_position().x < boundary.x || self.get_position().x > boundary.y
     }
 }
 
-pub fn collision<S: Object, T: Object>( object_1: &S, object_2: &T) -> bool{
+pub fn forgiving_collision<S: Object, T: Object>( enemy: &S, player: &T, forgiveness: f64) -> bool{
+    let enemy_parts: Vec<Part> = enemy.get_collision_parts().into_iter()
+        .map(|p| p.shrink(forgiveness)).collect();
+    parts_collide(&enemy_parts, &player.get_collision_parts())
+}
+
+pub fn parts_collide(parts_1: &[Part], parts_2: &[Part]) -> bool{
     let epsilon = 0.000000000001;
     let mut overlap: bool = false;
-    let parts_1 = object_1.get_collision_parts();
-    let parts_2 = object_2.get_collision_parts();
     'outer: for p_1 in parts_1.iter(){
         'inner: for p_2 in parts_2.iter(){
             if p_1.radial.y < p_2.radial.x || p_1.radial.x > p_2.radial.y{
@@ -59,25 +61,54 @@ pub fn collision<S: Object, T: Object>( object_1: &S, object_2: &T) -> bool{
 }
 
 
-#[derive(Clone,Copy)]
+#[derive(Clone,Copy,Serialize,Deserialize)]
 pub struct Part {
     pub radial: Point,
     pub angle: Point,
     pub color: [f64;4]
 }
 
+impl Part {
+    // Shrinks the part about its centre; full rings keep their angular extent
+    pub fn shrink(&self, fraction: f64) -> Part {
+        let keep = (1.0 - fraction).max(0.0).min(1.0);
+        let radial_center = (self.radial.x + self.radial.y) / 2.0;
+        let radial_half = (self.radial.y - self.radial.x) * keep / 2.0;
+        let mut angle = self.angle;
+        if self.angle.y - self.angle.x < 1.0 {
+            let angle_center = (self.angle.x + self.angle.y) / 2.0;
+            let angle_half = (self.angle.y - self.angle.x) * keep / 2.0;
+            angle = Point{x: angle_center - angle_half, y: angle_center + angle_half};
+        }
+        Part{radial: Point{x: radial_center - radial_half, y: radial_center + radial_half},
+             angle: angle,
+             color: self.color}
+    }
+
+    pub fn expand(&self, margin: Point) -> Part {
+        Part{radial: Point{x: self.radial.x - margin.x, y: self.radial.y + margin.x},
+             angle: Point{x: self.angle.x - margin.y, y: self.angle.y + margin.y},
+             color: self.color}
+    }
+
+    pub fn with_color(&self, color: [f64; 4]) -> Part {
+        Part{radial: self.radial, angle: self.angle, color: color}
+    }
+}
+
 impl From<Part> for PolarPixel {
     fn from(pol: Part) -> Self {
         PolarPixel {
             radial: [pol.radial.x, pol.radial.y],
             angle: [pol.angle.x, pol.angle.y],
-            color: [pol.color[0], pol.color[1], pol.color[2], pol.color[3]]
+            color: [pol.color[0], pol.color[1], pol.color[2], pol.color[3]],
+            fill: PixelFill::Flat
         }
     }
 }
 
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64
@@ -0,0 +1,353 @@
+/*
+Polar Particle Emitters for Debris, Sparks and Other Short-Lived Effects
+*/
+
+use super::object::{Part, Point};
+use super::rng::GameRng;
+use rand::distributions::IndependentSample;
+use rand::distributions::range::Range;
+use ::settings::warn;
+use toml;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::Read;
+
+// Keeps a flood of despawns from growing the render batch without bound
+const MAX_PARTICLES: usize = 4096;
+// Mixed into the run seed so that the particle stream does not mirror the game's
+const SEED_SALT: u64 = 0x5041_5254_4943_4c45;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EffectKind {
+    PlayerDeath,
+    FlareDespawn,
+    Graze,
+}
+
+// Positions are polar: x is radial and y is angle in turns, matching Part
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParticleEffect {
+    pub count: usize,
+    // Seconds, chosen uniformly between the two
+    pub lifetime: [f64; 2],
+    // Radial units per second, in a uniformly random direction
+    pub speed: [f64; 2],
+    // Scales the angular part of the velocity, in turns per radial unit
+    pub angular_scale: f64,
+    // Radial acceleration toward the sun; negative values push outwards
+    pub gravity: f64,
+    // Fraction of velocity lost per second
+    pub drag: f64,
+    // Multipliers on the emitter's colour, spread evenly over the particle's life
+    pub colors: Vec<[f64; 4]>,
+    // Half-widths in both radial units and turns, spread evenly over the particle's life
+    pub sizes: Vec<f64>,
+}
+
+impl Default for ParticleEffect {
+    fn default() -> Self {
+        ParticleEffect {
+            count: 10,
+            lifetime: [0.5, 1.0],
+            speed: [0.2, 0.5],
+            angular_scale: 1.0,
+            gravity: 0.0,
+            drag: 0.0,
+            colors: vec![[1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 0.0]],
+            sizes: vec![0.002],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectLibrary {
+    pub player_death: ParticleEffect,
+    pub flare_despawn: ParticleEffect,
+    pub graze: ParticleEffect,
+}
+
+impl Default for EffectLibrary {
+    fn default() -> Self {
+        EffectLibrary {
+            player_death: ParticleEffect {
+                count: 100,
+                lifetime: [6.0, 10.0],
+                speed: [1.0, 1.0],
+                angular_scale: 1.0,
+                gravity: 0.0,
+                drag: 0.0,
+                colors: vec![[1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 0.0]],
+                sizes: vec![0.002],
+            },
+            flare_despawn: ParticleEffect {
+                count: 6,
+                lifetime: [0.3, 0.6],
+                speed: [0.1, 0.3],
+                angular_scale: 0.2,
+                gravity: -0.5,
+                drag: 1.0,
+                colors: vec![[1.0, 1.0, 1.0, 0.8], [1.0, 1.0, 1.0, 0.0]],
+                sizes: vec![0.006, 0.002],
+            },
+            graze: ParticleEffect {
+                count: 8,
+                lifetime: [0.2, 0.4],
+                speed: [0.3, 0.6],
+                angular_scale: 0.3,
+                gravity: 0.0,
+                drag: 3.0,
+                colors: vec![[1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 0.6, 0.0]],
+                sizes: vec![0.004, 0.001],
+            },
+        }
+    }
+}
+
+impl EffectLibrary {
+    pub fn get(&self, kind: EffectKind) -> &ParticleEffect {
+        match kind {
+            EffectKind::PlayerDeath => &self.player_death,
+            EffectKind::FlareDespawn => &self.flare_despawn,
+            EffectKind::Graze => &self.graze,
+        }
+    }
+
+    // A missing file gives the built-in effects, and fields left out of an effect keep their built-in values
+    pub fn load(path: &str) -> EffectLibrary {
+        let builtin = EffectLibrary::default();
+        let mut content = String::new();
+        match File::open(path) {
+            Ok(mut f) => if let Err(e) = f.read_to_string(&mut content) {
+                warn(&format!("Failed to read effects file {}: {}", path, e));
+                return builtin;
+            },
+            Err(_) => return builtin,
+        };
+        let overrides: toml::Value = match content.parse() {
+            Ok(v) => v,
+            Err(e) => {
+                warn(&format!("Failed to parse effects file {}: {}", path, e));
+                return builtin;
+            }
+        };
+        let mut merged = toml::Value::try_from(&builtin).expect("Built-in effects always serialise");
+        if let (Some(merged), Some(overrides)) = (merged.as_table_mut(), overrides.as_table()) {
+            for (name, effect) in overrides.iter() {
+                match (merged.get_mut(name).and_then(|m| m.as_table_mut()), effect.as_table()) {
+                    (Some(target), Some(fields)) => for (key, value) in fields.iter() {
+                        target.insert(key.clone(), value.clone());
+                    },
+                    _ => warn(&format!("Unknown effect [{}] in {}", name, path)),
+                }
+            }
+        }
+        match merged.try_into() {
+            Ok(library) => library,
+            Err(e) => {
+                warn(&format!("Invalid effects file {}, using the built-in effects: {}", path, e));
+                builtin
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Particle {
+    position: Point,
+    velocity: Point,
+    age: f64,
+    lifetime: f64,
+    color: [f64; 4],
+    kind: EffectKind,
+}
+
+pub struct ParticleSystem {
+    pub library: EffectLibrary,
+    particles: Vec<Particle>,
+    // Separate from the game rng, so that effects never change how a seeded run plays out
+    rng: GameRng,
+}
+
+impl ParticleSystem {
+    pub fn new(library: EffectLibrary, seed: u64) -> ParticleSystem {
+        ParticleSystem {
+            library: library,
+            particles: Vec::new(),
+            rng: GameRng::from_seed(seed ^ SEED_SALT),
+        }
+    }
+
+    pub fn reset(&mut self, seed: u64) {
+        self.particles.clear();
+        self.rng = GameRng::from_seed(seed ^ SEED_SALT);
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn emit(&mut self, kind: EffectKind, position: Point, color: [f64; 4]) {
+        let effect = self.library.get(kind);
+        let unif = Range::new(0.0, 1.0);
+        for _ in 0..effect.count.min(MAX_PARTICLES - self.particles.len()) {
+            let direction = 2.0 * PI * unif.ind_sample(&mut self.rng);
+            let speed = effect.speed[0] + (effect.speed[1] - effect.speed[0]) * unif.ind_sample(&mut self.rng);
+            let lifetime = effect.lifetime[0] + (effect.lifetime[1] - effect.lifetime[0]) * unif.ind_sample(&mut self.rng);
+            self.particles.push(Particle {
+                position: position,
+                velocity: Point{x: speed * direction.cos(), y: speed * direction.sin() * effect.angular_scale},
+                age: 0.0,
+                lifetime: lifetime,
+                color: color,
+                kind: kind,
+            });
+        }
+    }
+
+    pub fn update(&mut self, t_step: f64) {
+        let library = &self.library;
+        for p in self.particles.iter_mut() {
+            let effect = library.get(p.kind);
+            p.velocity = p.velocity.mult((-effect.drag * t_step).exp());
+            p.velocity.x -= effect.gravity * t_step;
+            p.position = p.position + p.velocity.mult(t_step);
+            p.age += t_step;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+    }
+
+    pub fn extend_parts(&self, parts: &mut Vec<Part>) {
+        for p in self.particles.iter() {
+            let effect = self.library.get(p.kind);
+            let life = (p.age / p.lifetime).min(1.0);
+            let scale = sample_color(&effect.colors, life);
+            let size = sample_size(&effect.sizes, life);
+            parts.push(Part {
+                radial: Point{x: p.position.x - size, y: p.position.x + size},
+                angle: Point{x: p.position.y - size, y: p.position.y + size},
+                color: [p.color[0] * scale[0], p.color[1] * scale[1], p.color[2] * scale[2], p.color[3] * scale[3]]
+            });
+        }
+    }
+}
+
+// Finds the pair of evenly spaced stops either side of t and how far t sits between them
+fn stop_position(count: usize, t: f64) -> (usize, usize, f64) {
+    if count < 2 {
+        return (0, 0, 0.0);
+    }
+    let scaled = t * (count - 1) as f64;
+    let low = (scaled.floor() as usize).min(count - 2);
+    (low, low + 1, scaled - low as f64)
+}
+
+fn sample_color(stops: &[[f64; 4]], t: f64) -> [f64; 4] {
+    if stops.is_empty() {
+        return [1.0, 1.0, 1.0, 1.0];
+    }
+    let (a, b, f) = stop_position(stops.len(), t);
+    let mut color = stops[a];
+    for (c, target) in color.iter_mut().zip(stops[b].iter()) {
+        *c += (target - *c) * f;
+    }
+    color
+}
+
+fn sample_size(stops: &[f64], t: f64) -> f64 {
+    if stops.is_empty() {
+        return 0.0;
+    }
+    let (a, b, f) = stop_position(stops.len(), t);
+    stops[a] + (stops[b] - stops[a]) * f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn stops_are_spread_evenly_over_a_life() {
+        assert_eq!(stop_position(0, 0.5), (0, 0, 0.0));
+        assert_eq!(stop_position(1, 0.5), (0, 0, 0.0));
+        assert_eq!(stop_position(2, 0.0), (0, 1, 0.0));
+        let (a, b, f) = stop_position(3, 0.75);
+        assert_eq!((a, b), (1, 2));
+        assert!(close(f, 0.5));
+        // The end of a life stays on the last pair rather than running past it
+        assert_eq!(stop_position(3, 1.0), (1, 2, 1.0));
+    }
+
+    #[test]
+    fn colors_and_sizes_blend_between_stops() {
+        let colors = [[1.0, 1.0, 1.0, 1.0], [0.0, 0.5, 1.0, 0.0]];
+        let color = sample_color(&colors, 0.5);
+        assert!(close(color[0], 0.5) && close(color[1], 0.75) && close(color[2], 1.0) && close(color[3], 0.5));
+        assert_eq!(sample_color(&[], 0.5), [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(sample_color(&colors[..1], 0.9), colors[0]);
+
+        assert!(close(sample_size(&[0.004, 0.002, 0.0], 0.25), 0.003));
+        assert_eq!(sample_size(&[], 0.5), 0.0);
+        assert_eq!(sample_size(&[0.01], 0.7), 0.01);
+    }
+
+    #[test]
+    fn effect_files_override_only_the_fields_they_give() {
+        let path = env::temp_dir().join("polar-particles-test-effects.toml");
+        File::create(&path).unwrap()
+            .write_all(b"[graze]\ncount = 3\n\n[flare_despawn]\ncolors = [[1.0, 0.0, 0.0, 1.0]]\n").unwrap();
+        let library = EffectLibrary::load(&path.to_string_lossy());
+        fs::remove_file(&path).unwrap();
+
+        let builtin = EffectLibrary::default();
+        assert_eq!(library.graze.count, 3);
+        assert_eq!(library.graze.lifetime, builtin.graze.lifetime);
+        assert_eq!(library.graze.sizes, builtin.graze.sizes);
+        assert_eq!(library.flare_despawn.colors, vec![[1.0, 0.0, 0.0, 1.0]]);
+        assert_eq!(library.flare_despawn.count, builtin.flare_despawn.count);
+        assert_eq!(library.player_death.count, builtin.player_death.count);
+    }
+
+    #[test]
+    fn missing_or_invalid_effect_files_give_the_builtin_effects() {
+        let builtin = EffectLibrary::default();
+        assert_eq!(EffectLibrary::load("no-such-effects.toml").graze.count, builtin.graze.count);
+
+        let path = env::temp_dir().join("polar-particles-test-invalid.toml");
+        File::create(&path).unwrap().write_all(b"[graze]\ncount = \"many\"\n").unwrap();
+        let library = EffectLibrary::load(&path.to_string_lossy());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(library.graze.count, builtin.graze.count);
+    }
+
+    #[test]
+    fn emitting_stops_at_the_particle_cap() {
+        let mut system = ParticleSystem::new(EffectLibrary::default(), 1);
+        let count = system.library.player_death.count;
+        for _ in 0..(MAX_PARTICLES / count + 2) {
+            system.emit(EffectKind::PlayerDeath, Point{x: 1.0, y: 0.0}, [1.0; 4]);
+        }
+        assert_eq!(system.particles.len(), MAX_PARTICLES);
+
+        let mut parts = Vec::new();
+        system.extend_parts(&mut parts);
+        assert_eq!(parts.len(), MAX_PARTICLES);
+    }
+
+    #[test]
+    fn particles_expire_after_their_lifetime() {
+        let mut system = ParticleSystem::new(EffectLibrary::default(), 1);
+        system.emit(EffectKind::Graze, Point{x: 1.0, y: 0.0}, [1.0; 4]);
+        assert_eq!(system.particles.len(), system.library.graze.count);
+        system.update(system.library.graze.lifetime[1]);
+        assert!(system.particles.is_empty());
+    }
+}
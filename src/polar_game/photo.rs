@@ -0,0 +1,81 @@
+/*
+Photo Mode: Freezes the Run and Frees the Camera, and Tracks Screenshot Requests
+*/
+
+use super::controls::rising_edge;
+use gg::games::view_details::PolarViewDetails;
+
+// Turns, radial units and zoom factors (as a natural log) per second at full input
+const ROTATE_SPEED: f64 = 0.25;
+const RADIAL_SPEED: f64 = 1.0;
+const ZOOM_SPEED: f64 = 1.0;
+
+#[derive(Copy, Clone, Default)]
+pub struct PhotoInput {
+    pub rotate: f64,
+    pub radial: f64,
+    pub zoom: f64,
+}
+
+pub struct PhotoMode {
+    active: bool,
+    view: PolarViewDetails,
+    input: PhotoInput,
+    toggle_lock: bool,
+    capture_lock: bool,
+    capture_requested: bool,
+}
+
+impl PhotoMode {
+    pub fn new() -> PhotoMode {
+        PhotoMode {
+            active: false,
+            view: Default::default(),
+            input: PhotoInput::default(),
+            toggle_lock: false,
+            capture_lock: false,
+            capture_requested: false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // Entering photo mode starts the free camera from wherever the game camera is
+    pub fn update_toggle(&mut self, pressed: bool, camera: &PolarViewDetails) {
+        if rising_edge(pressed, &mut self.toggle_lock) {
+            self.active = !self.active;
+            self.view = camera.clone();
+            self.input = PhotoInput::default();
+        }
+    }
+
+    pub fn update_capture(&mut self, pressed: bool) {
+        if rising_edge(pressed, &mut self.capture_lock) {
+            self.capture_requested = true;
+        }
+    }
+
+    pub fn take_capture_request(&mut self) -> bool {
+        let requested = self.capture_requested;
+        self.capture_requested = false;
+        requested
+    }
+
+    pub fn set_input(&mut self, input: PhotoInput) {
+        self.input = input;
+    }
+
+    // Zoom scales the tunnel depth; in flat mode only rotation and radial shift have an effect
+    pub fn update(&mut self, real_step: f64) {
+        self.view.rotation_angle += self.input.rotate * ROTATE_SPEED * real_step;
+        self.view.radial_shift += self.input.radial * RADIAL_SPEED * real_step;
+        let zoom = (-self.input.zoom * ZOOM_SPEED * real_step).exp();
+        self.view.length_total = (self.view.length_total * zoom).max(self.view.length_circle);
+    }
+
+    pub fn get_view(&self) -> PolarViewDetails {
+        self.view.clone()
+    }
+}
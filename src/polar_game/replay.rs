@@ -0,0 +1,110 @@
+/*
+Records and Plays Back the Per-Frame Inputs of a Run
+*/
+
+use super::{GameSetup, InputKeys};
+use serde_json;
+use std::fs::File;
+use std::io::{Read, Write};
+
+pub const REPLAY_VERSION: u32 = 2;
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub t_step: f64,
+    pub jump_angle: f64,
+    pub jump_radial: f64,
+    pub reset: bool,
+    pub rewind: bool,
+    pub pause: bool,
+    // Present on the first frame after the settings menu changed the setup mid-run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub setup: Option<GameSetup>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub setup: GameSetup,
+    pub frames: Vec<ReplayFrame>,
+    #[serde(skip)]
+    pending_setup: Option<GameSetup>,
+}
+
+impl Replay {
+    pub fn new(setup: GameSetup) -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            setup: setup,
+            frames: Vec::new(),
+            pending_setup: None,
+        }
+    }
+
+    pub fn record(&mut self, t_step: f64, keys: &InputKeys) {
+        self.frames.push(ReplayFrame {
+            t_step: t_step,
+            jump_angle: keys.jump_angle,
+            jump_radial: keys.jump_radial,
+            reset: keys.reset,
+            rewind: keys.rewind,
+            pause: keys.pause,
+            setup: self.pending_setup.take(),
+        });
+    }
+
+    // Attached to the next recorded frame, so that playback changes the setup at the same point
+    pub fn record_setup(&mut self, setup: GameSetup) {
+        self.pending_setup = Some(setup);
+    }
+
+    pub fn get_duration(&self) -> f64 {
+        self.frames.iter().map(|f| f.t_step).sum()
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        let mut file = File::create(path).map_err(|e| format!("failed to create {}: {}", path, e))?;
+        file.write_all(content.as_bytes()).map_err(|e| format!("failed to write {}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Replay, String> {
+        let mut content = String::new();
+        let mut file = File::open(path).map_err(|e| format!("failed to open {}: {}", path, e))?;
+        file.read_to_string(&mut content).map_err(|e| format!("failed to read {}: {}", path, e))?;
+        let replay: Replay = serde_json::from_str(&content).map_err(|e| format!("malformed replay {}: {}", path, e))?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!("unsupported replay version {} (expected {})", replay.version, REPLAY_VERSION));
+        }
+        if replay.setup.seed.is_none() {
+            return Err("replay has no seed and cannot be played back".to_string());
+        }
+        Ok(replay)
+    }
+}
+
+pub struct ReplayPlayback {
+    replay: Replay,
+    position: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> ReplayPlayback {
+        ReplayPlayback {
+            replay: replay,
+            position: 0,
+        }
+    }
+
+    pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+        let frame = self.replay.frames.get(self.position).cloned();
+        if frame.is_some() {
+            self.position += 1;
+        }
+        frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.replay.frames.len()
+    }
+}
@@ -0,0 +1,127 @@
+/*
+Keeps Periodic Snapshots for Rewinding the Simulation in Practice Mode
+*/
+
+use super::snapshot::GameSnapshot;
+use std::collections::VecDeque;
+
+pub struct Rewind {
+    snapshots: VecDeque<GameSnapshot>,
+    interval: f64,
+    capacity: usize,
+    since_record: f64,
+    since_restore: f64,
+}
+
+impl Rewind {
+    pub fn new(length: f64, interval: f64) -> Rewind {
+        Rewind {
+            snapshots: VecDeque::new(),
+            interval: interval,
+            capacity: (length / interval).ceil().max(1.0) as usize,
+            since_record: interval,
+            since_restore: 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.since_record = self.interval;
+        self.since_restore = 0.0;
+    }
+
+    // Only records once per interval of game time
+    pub fn should_record(&mut self, game_step: f64) -> bool {
+        self.since_record += game_step;
+        self.since_record >= self.interval
+    }
+
+    pub fn record(&mut self, snapshot: GameSnapshot) {
+        self.since_record = 0.0;
+        self.since_restore = 0.0;
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.len() > self.capacity {
+            self.snapshots.pop_front();
+        }
+    }
+
+    // Steps backwards one snapshot per interval of real time, keeping the oldest. Snapshots are taken per
+    // interval of game time, so away from a game speed of 1.0 rewinding runs faster or slower than play did
+    pub fn step_back(&mut self, real_step: f64) -> Option<GameSnapshot> {
+        self.since_restore += real_step;
+        if self.since_restore < self.interval {
+            return None;
+        }
+        self.since_restore -= self.interval;
+        self.since_record = 0.0;
+        if self.snapshots.len() > 1 {
+            self.snapshots.pop_back()
+        } else {
+            self.snapshots.back().cloned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polar_game::PolarGameBuilder;
+
+    // Snapshots told apart by their flare counter
+    fn snapshots(count: u64) -> Vec<GameSnapshot> {
+        let base = PolarGameBuilder::default().with_seed(1).build_game().snapshot();
+        (0..count).map(|i| {
+            let mut snapshot = base.clone();
+            snapshot.next_flare_id = i;
+            snapshot
+        }).collect()
+    }
+
+    fn marker(snapshot: Option<GameSnapshot>) -> Option<u64> {
+        snapshot.map(|s| s.next_flare_id)
+    }
+
+    #[test]
+    fn records_once_per_interval_of_game_time() {
+        let mut rewind = Rewind::new(1.0, 0.25);
+        assert!(rewind.should_record(0.0));
+        rewind.record(snapshots(1).remove(0));
+        assert!(!rewind.should_record(0.125));
+        assert!(rewind.should_record(0.125));
+    }
+
+    #[test]
+    fn keeps_only_the_rewind_length() {
+        let mut rewind = Rewind::new(0.5, 0.25);
+        for snapshot in snapshots(5) {
+            rewind.record(snapshot);
+        }
+        assert_eq!(marker(rewind.step_back(0.25)), Some(4));
+        assert_eq!(marker(rewind.step_back(0.25)), Some(3));
+        assert_eq!(marker(rewind.step_back(0.25)), Some(3));
+    }
+
+    #[test]
+    fn steps_back_once_per_interval_of_real_time() {
+        let mut rewind = Rewind::new(1.0, 0.25);
+        for snapshot in snapshots(4) {
+            rewind.record(snapshot);
+        }
+        assert_eq!(marker(rewind.step_back(0.125)), None);
+        assert_eq!(marker(rewind.step_back(0.125)), Some(3));
+        // A long frame still only steps back once, carrying the rest over
+        assert_eq!(marker(rewind.step_back(0.375)), Some(2));
+        assert_eq!(marker(rewind.step_back(0.125)), Some(1));
+    }
+
+    #[test]
+    fn clear_forgets_every_snapshot() {
+        let mut rewind = Rewind::new(1.0, 0.25);
+        for snapshot in snapshots(3) {
+            rewind.record(snapshot);
+        }
+        rewind.clear();
+        assert!(rewind.should_record(0.0));
+        assert_eq!(marker(rewind.step_back(0.25)), None);
+    }
+}
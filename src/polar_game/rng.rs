@@ -0,0 +1,42 @@
+/*
+Seedable Random Number Generator Whose State Is Saved Along With the Game
+*/
+
+use rand::Rng;
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct GameRng {
+    state: [u32; 4],
+}
+
+impl GameRng {
+    // Expands the seed with splitmix64 so that small or zero seeds still give a good xorshift state
+    pub fn from_seed(seed: u64) -> GameRng {
+        let mut x = seed;
+        let mut state = [0u32; 4];
+        for s in state.iter_mut() {
+            x = x.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            *s = ((z ^ (z >> 31)) >> 32) as u32;
+        }
+        if state == [0; 4] {
+            state[0] = 1;
+        }
+        GameRng{state: state}
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        let x = self.state[0];
+        let t = x ^ (x << 11);
+        self.state[0] = self.state[1];
+        self.state[1] = self.state[2];
+        self.state[2] = self.state[3];
+        let w = self.state[3];
+        self.state[3] = w ^ (w >> 19) ^ (t ^ (t >> 8));
+        self.state[3]
+    }
+}
@@ -0,0 +1,373 @@
+/*
+Encodes a Run's Setup, Mode, Difficulty and Seed as a Short Shareable Code
+*/
+
+use super::GameSetup;
+use super::game_mode::GameMode;
+use std::fmt;
+
+const RUN_CODE_VERSION: u8 = 2;
+const ALPHABET: &'static [u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// Fields are only written when they differ from the defaults, which keeps the usual code short
+const RADIAL_MAX: u16 = 1 << 0;
+const START_RADIAL: u16 = 1 << 1;
+const START_ANGLE: u16 = 1 << 2;
+const WIDTH_RADIAL: u16 = 1 << 3;
+const WIDTH_ANGLE: u16 = 1 << 4;
+const SPAWN_RATE_BASE: u16 = 1 << 5;
+const SPAWN_RATE_GROWTH: u16 = 1 << 6;
+const FLARE_SPEED_MIN: u16 = 1 << 7;
+const FLARE_SPEED_RANGE: u16 = 1 << 8;
+const FLAT_MODE: u16 = 1 << 9;
+const GAME_SPEED: u16 = 1 << 10;
+const HITBOX_FORGIVENESS: u16 = 1 << 11;
+
+#[derive(Debug, PartialEq)]
+pub enum RunCodeError {
+    InvalidCharacter(char),
+    Truncated,
+    Checksum,
+    Version(u8),
+    UnknownMode(u8),
+    InvalidValue(&'static str),
+}
+
+impl fmt::Display for RunCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RunCodeError::InvalidCharacter(c) => write!(f, "invalid character '{}' in run code", c),
+            RunCodeError::Truncated => write!(f, "run code is too short"),
+            RunCodeError::Checksum => write!(f, "run code checksum does not match (was it copied fully?)"),
+            RunCodeError::Version(v) => write!(f, "unsupported run code version {} (expected {})", v, RUN_CODE_VERSION),
+            RunCodeError::UnknownMode(m) => write!(f, "unknown game mode {} in run code", m),
+            RunCodeError::InvalidValue(name) => write!(f, "run code has an invalid value for {}", name),
+        }
+    }
+}
+
+pub fn encode(setup: &GameSetup, seed: u64) -> String {
+    let defaults = GameSetup::default();
+    let mut mask: u16 = 0;
+    let mut values: Vec<f64> = Vec::new();
+    {
+        let mut optional = |bit: u16, value: f64, default: f64| {
+            if value != default {
+                mask |= bit;
+                values.push(value);
+            }
+        };
+        optional(RADIAL_MAX, setup.radial_max, defaults.radial_max);
+        optional(START_RADIAL, setup.player_start.x, defaults.player_start.x);
+        optional(START_ANGLE, setup.player_start.y, defaults.player_start.y);
+        optional(WIDTH_RADIAL, setup.player_width.x, defaults.player_width.x);
+        optional(WIDTH_ANGLE, setup.player_width.y, defaults.player_width.y);
+        optional(SPAWN_RATE_BASE, setup.difficulty.spawn_rate_base, defaults.difficulty.spawn_rate_base);
+        optional(SPAWN_RATE_GROWTH, setup.difficulty.spawn_rate_growth, defaults.difficulty.spawn_rate_growth);
+        optional(FLARE_SPEED_MIN, setup.difficulty.flare_speed_min, defaults.difficulty.flare_speed_min);
+        optional(FLARE_SPEED_RANGE, setup.difficulty.flare_speed_range, defaults.difficulty.flare_speed_range);
+        optional(GAME_SPEED, setup.game_speed, defaults.game_speed);
+        optional(HITBOX_FORGIVENESS, setup.hitbox_forgiveness, defaults.hitbox_forgiveness);
+    }
+    if !setup.tunnel_mode {
+        mask |= FLAT_MODE;
+    }
+
+    let mut bytes: Vec<u8> = vec![RUN_CODE_VERSION];
+    push_u16(&mut bytes, mask);
+    match setup.mode {
+        GameMode::Endless => bytes.push(0),
+        GameMode::TimeAttack { duration } => { bytes.push(1); push_u64(&mut bytes, duration.to_bits()); },
+        GameMode::Zen => bytes.push(2),
+        GameMode::DailyChallenge { date } => { bytes.push(3); push_u32(&mut bytes, date); },
+    }
+    push_u64(&mut bytes, seed);
+    for v in values {
+        push_u64(&mut bytes, v.to_bits());
+    }
+    let check = checksum(&bytes);
+    push_u16(&mut bytes, check);
+    to_base64(&bytes)
+}
+
+// Returns the decoded setup (built on the defaults) and the run's seed
+pub fn decode(code: &str) -> Result<(GameSetup, u64), RunCodeError> {
+    let bytes = from_base64(code.trim())?;
+    if bytes.len() < 3 {
+        return Err(RunCodeError::Truncated);
+    }
+    let (payload, check) = bytes.split_at(bytes.len() - 2);
+    if checksum(payload) != ((check[0] as u16) << 8 | check[1] as u16) {
+        return Err(RunCodeError::Checksum);
+    }
+
+    let mut reader = Reader { bytes: payload, position: 0 };
+    let version = reader.read_u8()?;
+    if version != RUN_CODE_VERSION {
+        return Err(RunCodeError::Version(version));
+    }
+    let mask = reader.read_u16()?;
+    let mut setup = GameSetup::default();
+    setup.mode = match reader.read_u8()? {
+        0 => GameMode::Endless,
+        1 => GameMode::TimeAttack { duration: reader.read_f64()? },
+        2 => GameMode::Zen,
+        3 => GameMode::DailyChallenge { date: reader.read_u32()? },
+        m => return Err(RunCodeError::UnknownMode(m)),
+    };
+    let seed = reader.read_u64()?;
+    setup.seed = Some(seed);
+
+    if mask & RADIAL_MAX != 0 { setup.radial_max = reader.read_f64()?; }
+    if mask & START_RADIAL != 0 { setup.player_start.x = reader.read_f64()?; }
+    if mask & START_ANGLE != 0 { setup.player_start.y = reader.read_f64()?; }
+    if mask & WIDTH_RADIAL != 0 { setup.player_width.x = reader.read_f64()?; }
+    if mask & WIDTH_ANGLE != 0 { setup.player_width.y = reader.read_f64()?; }
+    if mask & SPAWN_RATE_BASE != 0 { setup.difficulty.spawn_rate_base = reader.read_f64()?; }
+    if mask & SPAWN_RATE_GROWTH != 0 { setup.difficulty.spawn_rate_growth = reader.read_f64()?; }
+    if mask & FLARE_SPEED_MIN != 0 { setup.difficulty.flare_speed_min = reader.read_f64()?; }
+    if mask & FLARE_SPEED_RANGE != 0 { setup.difficulty.flare_speed_range = reader.read_f64()?; }
+    if mask & GAME_SPEED != 0 { setup.game_speed = reader.read_f64()?; }
+    if mask & HITBOX_FORGIVENESS != 0 { setup.hitbox_forgiveness = reader.read_f64()?; }
+    setup.tunnel_mode = mask & FLAT_MODE == 0;
+
+    if reader.position != payload.len() {
+        return Err(RunCodeError::InvalidValue("trailing data"));
+    }
+    validate(&setup)?;
+    Ok((setup, seed))
+}
+
+fn validate(setup: &GameSetup) -> Result<(), RunCodeError> {
+    let positive = |v: f64| v.is_finite() && v > 0.0;
+    if !positive(setup.radial_max) { return Err(RunCodeError::InvalidValue("radial_max")); }
+    if !positive(setup.player_width.x) || !positive(setup.player_width.y) {
+        return Err(RunCodeError::InvalidValue("player_width"));
+    }
+    let start = setup.player_start;
+    if !start.x.is_finite() || !start.y.is_finite() || start.x < 0.0 || start.x > setup.radial_max {
+        return Err(RunCodeError::InvalidValue("player_start"));
+    }
+    let difficulty = setup.difficulty;
+    if !positive(difficulty.spawn_rate_base) || !difficulty.spawn_rate_growth.is_finite() || difficulty.spawn_rate_growth < 0.0 {
+        return Err(RunCodeError::InvalidValue("spawn rate"));
+    }
+    if !positive(difficulty.flare_speed_min) || !difficulty.flare_speed_range.is_finite() || difficulty.flare_speed_range < 0.0 {
+        return Err(RunCodeError::InvalidValue("flare speed"));
+    }
+    if !positive(setup.game_speed) { return Err(RunCodeError::InvalidValue("game_speed")); }
+    if !setup.hitbox_forgiveness.is_finite() || setup.hitbox_forgiveness < 0.0 || setup.hitbox_forgiveness > 1.0 {
+        return Err(RunCodeError::InvalidValue("hitbox_forgiveness"));
+    }
+    match setup.mode {
+        GameMode::TimeAttack { duration } if !positive(duration) => Err(RunCodeError::InvalidValue("time attack duration")),
+        GameMode::DailyChallenge { date } if !valid_date(date) => Err(RunCodeError::InvalidValue("daily challenge date")),
+        _ => Ok(())
+    }
+}
+
+// Dates are written as YYYYMMDD, the same as GameMode::daily_challenge makes them
+fn valid_date(date: u32) -> bool {
+    let (year, month, day) = (date / 10000, date / 100 % 100, date % 100);
+    let days_in_month = match month {
+        2 => if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) { 29 } else { 28 },
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    (1970..=9999).contains(&year) && (1..=12).contains(&month) && day >= 1 && day <= days_in_month
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_u8(&mut self) -> Result<u8, RunCodeError> {
+        let byte = *self.bytes.get(self.position).ok_or(RunCodeError::Truncated)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, RunCodeError> {
+        Ok((self.read_u8()? as u16) << 8 | self.read_u8()? as u16)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, RunCodeError> {
+        Ok((self.read_u16()? as u32) << 16 | self.read_u16()? as u32)
+    }
+
+    fn read_u64(&mut self) -> Result<u64, RunCodeError> {
+        let mut value: u64 = 0;
+        for _ in 0..8 {
+            value = value << 8 | self.read_u8()? as u64;
+        }
+        Ok(value)
+    }
+
+    fn read_f64(&mut self) -> Result<f64, RunCodeError> {
+        Ok(f64::from_bits(self.read_u64()?))
+    }
+}
+
+fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.push((value >> 8) as u8);
+    bytes.push(value as u8);
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    push_u16(bytes, (value >> 16) as u16);
+    push_u16(bytes, value as u16);
+}
+
+fn push_u64(bytes: &mut Vec<u8>, value: u64) {
+    for i in (0..8).rev() {
+        bytes.push((value >> (i * 8)) as u8);
+    }
+}
+
+// Fletcher-16
+fn checksum(bytes: &[u8]) -> u16 {
+    let (mut sum_1, mut sum_2) = (0u16, 0u16);
+    for b in bytes {
+        sum_1 = (sum_1 + *b as u16) % 255;
+        sum_2 = (sum_2 + sum_1) % 255;
+    }
+    sum_2 << 8 | sum_1
+}
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..(chunk.len() + 1) {
+            output.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    output
+}
+
+fn from_base64(code: &str) -> Result<Vec<u8>, RunCodeError> {
+    let mut values: Vec<u32> = Vec::new();
+    for c in code.chars() {
+        match ALPHABET.iter().position(|&a| a as char == c) {
+            Some(v) => values.push(v as u32),
+            None => return Err(RunCodeError::InvalidCharacter(c)),
+        }
+    }
+    let mut bytes: Vec<u8> = Vec::new();
+    for chunk in values.chunks(4) {
+        if chunk.len() == 1 {
+            return Err(RunCodeError::Truncated);
+        }
+        let mut n: u32 = 0;
+        for i in 0..4 {
+            n = n << 6 | *chunk.get(i).unwrap_or(&0);
+        }
+        for i in 0..(chunk.len() - 1) {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Re-encodes a payload with a valid checksum, so that only the payload itself is under test
+    fn sign(payload: &[u8]) -> String {
+        let mut bytes = payload.to_vec();
+        let check = checksum(&bytes);
+        push_u16(&mut bytes, check);
+        to_base64(&bytes)
+    }
+
+    fn error(code: &str) -> RunCodeError {
+        decode(code).err().expect("expected the run code to be rejected")
+    }
+
+    fn payload(code: &str) -> Vec<u8> {
+        let mut bytes = from_base64(code).unwrap();
+        let length = bytes.len() - 2;
+        bytes.truncate(length);
+        bytes
+    }
+
+    #[test]
+    fn every_mode_round_trips() {
+        let modes = [GameMode::Endless, GameMode::TimeAttack { duration: 90.0 },
+                     GameMode::Zen, GameMode::DailyChallenge { date: 20240229 }];
+        for mode in modes.iter() {
+            let mut setup = GameSetup::default();
+            setup.mode = *mode;
+            setup.radial_max = 6.0;
+            setup.tunnel_mode = false;
+            setup.game_speed = 0.75;
+            setup.hitbox_forgiveness = 0.3;
+            setup.difficulty.flare_speed_min = 0.4;
+            let (decoded, seed) = decode(&encode(&setup, 0xdead_beef_1234)).unwrap();
+            assert_eq!(seed, 0xdead_beef_1234);
+            assert_eq!(decoded.seed, Some(seed));
+            assert_eq!(decoded.mode, *mode);
+            assert_eq!(decoded.radial_max, 6.0);
+            assert!(!decoded.tunnel_mode);
+            assert_eq!(decoded.game_speed, 0.75);
+            assert_eq!(decoded.hitbox_forgiveness, 0.3);
+            assert_eq!(decoded.difficulty.flare_speed_min, 0.4);
+            assert_eq!(decoded.player_start.x, setup.player_start.x);
+        }
+    }
+
+    #[test]
+    fn default_setup_round_trips() {
+        let (decoded, _) = decode(&encode(&GameSetup::default(), 1)).unwrap();
+        assert!(decoded.is_ranked());
+        assert!(decoded.tunnel_mode);
+    }
+
+    #[test]
+    fn rejects_a_bad_checksum() {
+        let mut bytes = from_base64(&encode(&GameSetup::default(), 1)).unwrap();
+        bytes[4] ^= 0x01;
+        assert_eq!(error(&to_base64(&bytes)), RunCodeError::Checksum);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let mut bytes = payload(&encode(&GameSetup::default(), 1));
+        bytes[0] = RUN_CODE_VERSION + 1;
+        assert_eq!(error(&sign(&bytes)), RunCodeError::Version(RUN_CODE_VERSION + 1));
+    }
+
+    #[test]
+    fn rejects_truncated_codes() {
+        let mut bytes = payload(&encode(&GameSetup::default(), 1));
+        bytes.pop();
+        assert_eq!(error(&sign(&bytes)), RunCodeError::Truncated);
+        assert_eq!(error("AB"), RunCodeError::Truncated);
+        assert_eq!(error("ABCDE"), RunCodeError::Truncated);
+    }
+
+    #[test]
+    fn rejects_oversized_codes() {
+        let mut bytes = payload(&encode(&GameSetup::default(), 1));
+        bytes.push(0);
+        assert_eq!(error(&sign(&bytes)), RunCodeError::InvalidValue("trailing data"));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for date in [20241301, 20240230, 20230229, 0, 99999999].iter() {
+            let mut setup = GameSetup::default();
+            setup.mode = GameMode::DailyChallenge { date: *date };
+            assert_eq!(error(&encode(&setup, 1)), RunCodeError::InvalidValue("daily challenge date"));
+        }
+    }
+
+    #[test]
+    fn rejects_characters_outside_the_alphabet() {
+        assert_eq!(error("AB+D"), RunCodeError::InvalidCharacter('+'));
+    }
+}
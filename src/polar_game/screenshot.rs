@@ -0,0 +1,131 @@
+/*
+Saves Frames to Timestamped PNG Files Through the Software Renderer
+*/
+
+use ::rendering::{PolarRenderable, SoftwareRenderer};
+use ::rendering::software::Scene;
+use ::rendering::software::text::load_font;
+use ::rendering::assets::{TextureLayer, load_texture_layers};
+use ::settings::CaptureConfig;
+use gg::rendering::Renderer;
+use gg::games::view_details::{PolarViewDetails, ViewDetails};
+use gg::debug::*;
+use rusttype::Font;
+use time;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::thread;
+use std::thread::JoinHandle;
+
+// What a capture renderer loads from disk, loaded once and shared by every renderer built from it
+pub struct CaptureAssets {
+    font: Option<Font<'static>>,
+    textures: Vec<TextureLayer>,
+}
+
+impl CaptureAssets {
+    pub fn load(config: &CaptureConfig, texture_paths: &[String]) -> CaptureAssets {
+        let mut font = None;
+        if !config.font_path.is_empty() {
+            font = load_font(&config.font_path);
+            if font.is_none() {
+                debug(&format!("Failed to load capture font {}, text will be left out", config.font_path));
+            }
+        }
+        let textures = load_texture_layers(texture_paths).unwrap_or_else(|e| {
+            debug(&format!("Warning: {}, textured pixels will show their tint only", e));
+            Vec::new()
+        });
+        CaptureAssets {
+            font: font,
+            textures: textures,
+        }
+    }
+
+    pub fn build_renderer(&self, width: u32, height: u32) -> SoftwareRenderer {
+        let mut renderer = SoftwareRenderer::new(width, height);
+        if let Some(ref font) = self.font {
+            renderer.with_font(font.clone());
+        }
+        renderer.with_textures(self.textures.clone());
+        renderer
+    }
+}
+
+struct CaptureJob {
+    scene: Scene,
+    view: PolarViewDetails,
+    size: (u32, u32),
+    path: String,
+}
+
+// A full-resolution software render and PNG encode take longer than a frame, so they run on a
+// background thread and the game collects the results later
+pub struct CaptureWorker {
+    directory: String,
+    jobs: Sender<CaptureJob>,
+    results: Receiver<Result<String, String>>,
+    thread: JoinHandle<()>,
+}
+
+impl CaptureWorker {
+    pub fn new(config: &CaptureConfig, texture_paths: &[String]) -> CaptureWorker {
+        let assets = CaptureAssets::load(config, texture_paths);
+        let (jobs, pending) = channel::<CaptureJob>();
+        let (finished, results) = channel();
+        let thread = thread::spawn(move || {
+            let mut renderer: Option<SoftwareRenderer> = None;
+            for job in pending.iter() {
+                if renderer.as_ref().map_or(true, |r| r.get_image().get_size() != job.size) {
+                    renderer = Some(assets.build_renderer(job.size.0, job.size.1));
+                }
+                let renderer = renderer.as_mut().unwrap();
+                renderer.set_worldview(ViewDetails::Polar(job.view));
+                renderer.load_scene(job.scene);
+                renderer.render();
+                let result = renderer.save_png(&job.path)
+                    .map(|_| job.path.clone())
+                    .map_err(|e| format!("{}: {}", job.path, e));
+                if finished.send(result).is_err() {
+                    break;
+                }
+            }
+        });
+        CaptureWorker {
+            directory: config.directory.clone(),
+            jobs: jobs,
+            results: results,
+            thread: thread,
+        }
+    }
+
+    // The file is named for when the capture was asked for, not when it is written
+    pub fn request(&self, renderables: Vec<Box<PolarRenderable>>, view: PolarViewDetails, width: u32, height: u32) -> Result<(), String> {
+        fs::create_dir_all(&self.directory).map_err(|e| format!("{}: {}", self.directory, e))?;
+        let now = time::now();
+        let stamp = time::strftime("%Y%m%d-%H%M%S", &now).unwrap_or_else(|_| "unknown".to_string());
+        let file_name = format!("screenshot-{}-{:03}.png", stamp, now.tm_nsec / 1_000_000);
+        let job = CaptureJob {
+            scene: Scene::from_renderables(renderables),
+            view: view,
+            size: (width, height),
+            path: Path::new(&self.directory).join(file_name).to_string_lossy().into_owned(),
+        };
+        self.jobs.send(job).map_err(|_| "the capture thread has stopped".to_string())
+    }
+
+    // Paths of the captures written since the last call, or why they failed
+    pub fn take_results(&self) -> Vec<Result<String, String>> {
+        self.results.try_iter().collect()
+    }
+
+    // Waits for the captures still queued, so that none are lost on exit
+    pub fn finish(self) -> Vec<Result<String, String>> {
+        drop(self.jobs);
+        if self.thread.join().is_err() {
+            return vec![Err("the capture thread panicked".to_string())];
+        }
+        self.results.try_iter().collect()
+    }
+}
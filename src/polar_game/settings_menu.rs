@@ -0,0 +1,169 @@
+/*
+In-Game Settings Screen, Written Back to the Settings File When Closed
+*/
+
+use ::settings::Settings;
+use super::theme;
+use gg::rendering::{TextAlign, PlainText};
+use na::{Vector2, Vector3, Vector4, Rotation2};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Item {
+    Fullscreen,
+    Resolution,
+    GameSpeed,
+    HitboxForgiveness,
+    ShowHitboxes,
+    TunnelMode,
+    PracticeMode,
+    MovementSpeed,
+    GamepadDeadzone,
+    MouseControl,
+    Camera,
+    ScreenShake,
+    Theme,
+    ReducedEffects,
+    Bloom,
+    Vignette,
+    ChromaticAberration,
+    Scanlines,
+    MotionTrails,
+}
+
+// The menu lists its items in this order
+const ITEMS: &'static [Item] = &[Item::Fullscreen, Item::Resolution,
+                                 Item::GameSpeed, Item::HitboxForgiveness, Item::ShowHitboxes, Item::TunnelMode, Item::PracticeMode,
+                                 Item::MovementSpeed, Item::GamepadDeadzone, Item::MouseControl,
+                                 Item::Camera, Item::ScreenShake, Item::Theme,
+                                 Item::ReducedEffects, Item::Bloom, Item::Vignette, Item::ChromaticAberration, Item::Scanlines,
+                                 Item::MotionTrails];
+const RESOLUTIONS: [(u32, u32); 5] = [(1280, 720), (1366, 768), (1600, 900), (1920, 1080), (2560, 1440)];
+
+pub struct SettingsMenu {
+    pub settings: Settings,
+    path: String,
+    open: bool,
+    selected: usize,
+    previous_keys: [bool; 5],
+}
+
+impl SettingsMenu {
+    pub fn new(settings: Settings, path: String) -> SettingsMenu {
+        SettingsMenu {
+            settings: settings,
+            path: path,
+            open: false,
+            selected: 0,
+            previous_keys: [false; 5],
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    // Returns true when the menu has just been closed and the settings were saved
+    pub fn update(&mut self, toggle: bool, up: bool, down: bool, left: bool, right: bool) -> bool {
+        let keys = [toggle, up, down, left, right];
+        let pressed: Vec<bool> = keys.iter().zip(self.previous_keys.iter()).map(|(k, p)| *k && !*p).collect();
+        self.previous_keys = keys;
+
+        if pressed[0] {
+            self.open = !self.open;
+            if !self.open {
+                self.settings.save(&self.path);
+                return true;
+            }
+            return false;
+        }
+        if !self.open { return false; }
+
+        if pressed[1] { self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len(); }
+        if pressed[2] { self.selected = (self.selected + 1) % ITEMS.len(); }
+        if pressed[3] { self.adjust(-1); }
+        if pressed[4] { self.adjust(1); }
+        false
+    }
+
+    fn adjust(&mut self, direction: i32) {
+        let step = direction as f64 * 0.05;
+        let settings = &mut self.settings;
+        match ITEMS[self.selected] {
+            Item::Fullscreen => settings.display.fullscreen = !settings.display.fullscreen,
+            Item::Resolution => {
+                let current = RESOLUTIONS.iter()
+                    .position(|r| *r == (settings.display.width, settings.display.height))
+                    .unwrap_or(3) as i32;
+                let next = (current + direction).max(0).min(RESOLUTIONS.len() as i32 - 1) as usize;
+                settings.display.width = RESOLUTIONS[next].0;
+                settings.display.height = RESOLUTIONS[next].1;
+            },
+            // Rounded so that stepping back lands exactly on the ranked values of 1.0 and 0.0
+            Item::GameSpeed => settings.gameplay.game_speed = round_step(settings.gameplay.game_speed + step).max(0.25).min(2.0),
+            Item::HitboxForgiveness => settings.gameplay.hitbox_forgiveness = round_step(settings.gameplay.hitbox_forgiveness + step).max(0.0).min(0.9),
+            Item::ShowHitboxes => settings.gameplay.show_hitboxes = !settings.gameplay.show_hitboxes,
+            Item::TunnelMode => settings.gameplay.tunnel_mode = !settings.gameplay.tunnel_mode,
+            Item::PracticeMode => settings.gameplay.practice_mode = !settings.gameplay.practice_mode,
+            Item::MovementSpeed => settings.input.movement_speed = (settings.input.movement_speed + step).max(0.05).min(2.0),
+            Item::GamepadDeadzone => settings.input.stick.radial_deadzone = (settings.input.stick.radial_deadzone + step).max(0.0).min(0.9),
+            Item::MouseControl => settings.input.mouse.enabled = !settings.input.mouse.enabled,
+            Item::Camera => settings.camera.mode = settings.camera.mode.next(direction),
+            Item::ScreenShake => settings.camera.screen_shake = !settings.camera.screen_shake,
+            Item::Theme => settings.appearance.theme = theme::next_theme(&settings.appearance.theme,
+                                                                         &settings.appearance.theme_directory, direction),
+            Item::ReducedEffects => settings.post_process.reduced_effects = !settings.post_process.reduced_effects,
+            Item::Bloom => settings.post_process.bloom = !settings.post_process.bloom,
+            Item::Vignette => settings.post_process.vignette = !settings.post_process.vignette,
+            Item::ChromaticAberration => settings.post_process.chromatic_aberration = !settings.post_process.chromatic_aberration,
+            Item::Scanlines => settings.post_process.scanlines = !settings.post_process.scanlines,
+            Item::MotionTrails => settings.trails.enabled = !settings.trails.enabled,
+        }
+    }
+
+    fn get_item_string(&self, item: Item) -> String {
+        let s = &self.settings;
+        let on_off = |b: bool| if b { "On" } else { "Off" };
+        match item {
+            Item::Fullscreen => format!("Fullscreen: {}", on_off(s.display.fullscreen)),
+            Item::Resolution => format!("Resolution: {}x{}", s.display.width, s.display.height),
+            Item::GameSpeed => format!("Game Speed: {0:.2}", s.gameplay.game_speed),
+            Item::HitboxForgiveness => format!("Hitbox Forgiveness: {0:.0}%", s.gameplay.hitbox_forgiveness * 100.0),
+            Item::ShowHitboxes => format!("Show Hitboxes: {}", on_off(s.gameplay.show_hitboxes)),
+            Item::TunnelMode => format!("Tunnel Mode: {}", on_off(s.gameplay.tunnel_mode)),
+            Item::PracticeMode => format!("Practice Mode (Rewind): {}", on_off(s.gameplay.practice_mode)),
+            Item::MovementSpeed => format!("Movement Speed: {0:.2}", s.input.movement_speed),
+            Item::GamepadDeadzone => format!("Gamepad Deadzone: {0:.2}", s.input.stick.radial_deadzone),
+            Item::MouseControl => format!("Mouse Control: {}", on_off(s.input.mouse.enabled)),
+            Item::Camera => format!("Camera: {}", s.camera.mode.get_name()),
+            Item::ScreenShake => format!("Screen Shake: {}", on_off(s.camera.screen_shake)),
+            Item::Theme => format!("Theme: {}", s.appearance.theme),
+            Item::ReducedEffects => format!("Reduced Effects: {}", on_off(s.post_process.reduced_effects)),
+            Item::Bloom => format!("Bloom: {}", on_off(s.post_process.bloom)),
+            Item::Vignette => format!("Vignette: {}", on_off(s.post_process.vignette)),
+            Item::ChromaticAberration => format!("Chromatic Aberration: {}", on_off(s.post_process.chromatic_aberration)),
+            Item::Scanlines => format!("CRT Scanlines: {}", on_off(s.post_process.scanlines)),
+            Item::MotionTrails => format!("Motion Trails: {}", on_off(s.trails.enabled)),
+        }
+    }
+
+    pub fn get_texts(&self) -> Vec<PlainText> {
+        let mut lines = vec!["Settings (display changes apply on restart)".to_string()];
+        for (i, item) in ITEMS.iter().map(|&item| self.get_item_string(item)).enumerate() {
+            let marker = if i == self.selected { "> " } else { "  " };
+            lines.push(format!("{}{}", marker, item));
+        }
+        lines.into_iter().enumerate().map(|(i, content)| PlainText {
+            content: content,
+            position: Vector3::new(0.0, 0.65 - 0.08 * i as f64, 0.0),
+            scale: Vector2::new(0.07, 0.07),
+            transform: *Rotation2::new(0.0).matrix(),
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            fixed: true,
+            align: TextAlign::Center
+        }).collect()
+    }
+}
+
+fn round_step(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
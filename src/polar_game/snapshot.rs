@@ -0,0 +1,173 @@
+/*
+Saves and Restores the Full Simulation State to a Versioned File
+*/
+
+use super::{GameSetup, GameState, Times};
+use super::player::Player;
+use super::flare::Flare;
+use super::sun::Sun;
+use super::time_scale::TimeScale;
+use super::kill_cam::KillCam;
+use super::rng::GameRng;
+use serde_json;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+
+pub const SNAPSHOT_VERSION: u32 = 8;
+
+// Where the suspend key saves the run, for --resume to pick up
+pub const SUSPEND_PATH: &'static str = "suspended.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub version: u32,
+    pub setup: GameSetup,
+    pub player: Player,
+    pub flares: Vec<Flare>,
+    pub sun: Sun,
+    pub time: Times,
+    pub time_scale: TimeScale,
+    pub state: GameState,
+    pub kill_cam: KillCam,
+    pub score: f64,
+    pub next_flare_id: u64,
+    pub rng: GameRng,
+    pub run_seed: u64,
+}
+
+#[derive(Deserialize)]
+struct SnapshotHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    Version(u32),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::Io(ref e) => write!(f, "failed to access snapshot file: {}", e),
+            SnapshotError::Format(ref e) => write!(f, "malformed snapshot: {}", e),
+            SnapshotError::Version(v) => write!(f, "unsupported snapshot version {} (expected {})", v, SNAPSHOT_VERSION),
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self { SnapshotError::Io(e) }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self { SnapshotError::Format(e) }
+}
+
+impl GameSnapshot {
+    pub fn to_string(&self) -> String {
+        serde_json::to_string_pretty(self).expect("failed to serialise snapshot")
+    }
+
+    pub fn from_str(content: &str) -> Result<GameSnapshot, SnapshotError> {
+        let header: SnapshotHeader = serde_json::from_str(content)?;
+        if header.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version(header.version));
+        }
+        Ok(serde_json::from_str(content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SnapshotError> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_string().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<GameSnapshot, SnapshotError> {
+        let mut file = File::open(path)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        GameSnapshot::from_str(&content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polar_game::{PolarGame, PolarGameBuilder};
+    use gg::games::Game;
+    use std::env;
+    use std::fs;
+
+    fn run(game: &mut PolarGame, steps: usize) {
+        for _ in 0..steps {
+            game.update_logic(1.0 / 60.0);
+        }
+    }
+
+    #[test]
+    fn restored_game_continues_identically() {
+        let mut original = PolarGameBuilder::default().with_seed(42).build_game();
+        run(&mut original, 600);
+
+        let saved = original.snapshot().to_string();
+        let mut restored = PolarGameBuilder::default().with_seed(7).build_game();
+        restored.restore(GameSnapshot::from_str(&saved).unwrap());
+        assert_eq!(restored.snapshot().to_string(), saved);
+
+        run(&mut original, 600);
+        run(&mut restored, 600);
+        assert_eq!(original.snapshot().to_string(), restored.snapshot().to_string());
+    }
+
+    #[test]
+    fn first_run_matches_its_resets() {
+        let mut game = PolarGameBuilder::default().with_seed(42).build_game();
+        game.init();
+        let fresh = game.snapshot().to_string();
+        run(&mut game, 300);
+        game.reset();
+        assert_eq!(game.snapshot().to_string(), fresh);
+    }
+
+    #[test]
+    fn restored_runs_are_unranked() {
+        let original = PolarGameBuilder::default().build_game();
+        let mut restored = PolarGameBuilder::default().build_game();
+        assert!(!restored.high_score.get_score_text().content.contains("Unranked"));
+        restored.restore(original.snapshot());
+        assert!(restored.high_score.get_score_text().content.contains("Unranked"));
+    }
+
+    #[test]
+    fn suspended_runs_resume_from_file() {
+        let mut original = PolarGameBuilder::default().with_seed(42).build_game();
+        run(&mut original, 600);
+
+        let path = env::temp_dir().join("polar-snapshot-test-suspended.json");
+        let path = path.to_string_lossy();
+        original.snapshot().save(&path).unwrap();
+        let mut resumed = PolarGameBuilder::default().with_snapshot(GameSnapshot::load(&path).unwrap()).build_game();
+        fs::remove_file(&*path).unwrap();
+        resumed.init();
+        assert_eq!(resumed.snapshot().to_string(), original.snapshot().to_string());
+
+        run(&mut original, 600);
+        run(&mut resumed, 600);
+        assert_eq!(original.snapshot().to_string(), resumed.snapshot().to_string());
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let game = PolarGameBuilder::default().with_seed(1).build_game();
+        let mut snapshot = game.snapshot();
+        snapshot.version = SNAPSHOT_VERSION + 1;
+        match GameSnapshot::from_str(&snapshot.to_string()) {
+            Err(SnapshotError::Version(v)) => assert_eq!(v, SNAPSHOT_VERSION + 1),
+            _ => panic!("expected a version error"),
+        }
+    }
+}
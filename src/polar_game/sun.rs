@@ -6,7 +6,7 @@ Handles the Sun Enemy Type
 use super::object::{Part,Object,Point};
 use super::enemy::Enemy;
 
-#[derive(Copy,Clone)]
+#[derive(Copy,Clone,Serialize,Deserialize)]
 pub struct Sun{
     part: Part,
 }
@@ -24,6 +24,11 @@ impl Object for Sun{
         part_vec.push(self.part);
         part_vec
     }
+
+    // The sun is a solid disc with no soft edge, so it hits exactly where it is drawn
+    fn get_collision_parts(&self) -> Vec<Part>{
+        self.get_render_parts()
+    }
 }
 
 impl Sun{
@@ -0,0 +1,202 @@
+/*
+Colour Themes with a Palette Slot for Every Object and the Background
+*/
+
+use toml;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+pub const BUILTIN_THEMES: [&'static str; 3] = ["classic", "high-contrast", "colourblind"];
+
+// Colours are RGBA; alpha is kept as given, so the hitbox slot stays see-through
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    pub background: [f64; 4],
+    pub frame: [f64; 4],
+    // A layer of appearance.textures to draw the frame grid with, tinted by the frame colour
+    pub frame_texture: Option<u32>,
+    pub player: [f64; 4],
+    pub flare: [f64; 4],
+    // Flares fade from the flare colour at their head to this at their tail
+    pub flare_tail: [f64; 4],
+    pub sun: [f64; 4],
+    pub highlight: [f64; 4],
+    pub hitbox: [f64; 4],
+    pub text: [f64; 4],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::classic()
+    }
+}
+
+impl Theme {
+    pub fn classic() -> Theme {
+        Theme {
+            name: "classic".to_string(),
+            background: [0.0, 0.0, 0.0, 1.0],
+            frame: [0.1, 0.1, 1.0, 1.0],
+            frame_texture: None,
+            player: [1.0, 1.0, 1.0, 1.0],
+            flare: [0.8, 0.3, 0.0, 1.0],
+            flare_tail: [0.5, 0.05, 0.0, 0.5],
+            sun: [0.9, 0.5, 0.2, 1.0],
+            highlight: [1.0, 1.0, 0.2, 1.0],
+            hitbox: [0.0, 1.0, 0.0, 0.4],
+            text: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    // Fully saturated hazards against a dim frame
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name: "high-contrast".to_string(),
+            background: [0.0, 0.0, 0.0, 1.0],
+            frame: [0.3, 0.3, 0.3, 1.0],
+            frame_texture: None,
+            player: [1.0, 1.0, 1.0, 1.0],
+            flare: [1.0, 1.0, 0.0, 1.0],
+            flare_tail: [0.8, 0.8, 0.0, 0.8],
+            sun: [1.0, 0.0, 0.0, 1.0],
+            highlight: [1.0, 0.0, 1.0, 1.0],
+            hitbox: [0.0, 1.0, 1.0, 0.5],
+            text: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    // Okabe-Ito colours, which stay distinct under the common forms of colour blindness
+    pub fn colourblind() -> Theme {
+        Theme {
+            name: "colourblind".to_string(),
+            background: [0.0, 0.0, 0.0, 1.0],
+            frame: [0.0, 0.45, 0.7, 1.0],
+            frame_texture: None,
+            player: [1.0, 1.0, 1.0, 1.0],
+            flare: [0.9, 0.6, 0.0, 1.0],
+            flare_tail: [0.6, 0.35, 0.0, 0.6],
+            sun: [0.94, 0.89, 0.26, 1.0],
+            highlight: [0.8, 0.47, 0.65, 1.0],
+            hitbox: [0.0, 0.62, 0.45, 0.5],
+            text: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "classic" => Some(Theme::classic()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            "colourblind" => Some(Theme::colourblind()),
+            _ => None
+        }
+    }
+
+    // Slots missing from the file keep their classic colours
+    pub fn load(path: &Path) -> Result<Theme, String> {
+        let mut content = String::new();
+        File::open(path).and_then(|mut f| f.read_to_string(&mut content))
+            .map_err(|e| format!("Failed to read theme file {}: {}", path.display(), e))?;
+        let mut theme: Theme = toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse theme file {}: {}", path.display(), e))?;
+        if let Some(stem) = path.file_stem() {
+            theme.name = stem.to_string_lossy().into_owned();
+        }
+        Ok(theme)
+    }
+
+    // Built-in names take priority over files of the same name in the theme directory
+    pub fn find(name: &str, directory: &str) -> Result<Theme, String> {
+        match Theme::builtin(name) {
+            Some(theme) => Ok(theme),
+            None => Theme::load(&Path::new(directory).join(format!("{}.toml", name)))
+        }
+    }
+}
+
+// The built-in themes followed by the theme files in the directory, in name order
+pub fn available_themes(directory: &str) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|n| n.to_string()).collect();
+    let mut files: Vec<String> = match fs::read_dir(directory) {
+        Ok(entries) => entries.filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |ext| ext == "toml"))
+            .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+            .filter(|n| !names.contains(n))
+            .collect(),
+        Err(_) => Vec::new()
+    };
+    files.sort();
+    names.extend(files);
+    names
+}
+
+// Wraps around in both directions; an unknown current theme starts from the first
+pub fn next_theme(current: &str, directory: &str, direction: i32) -> String {
+    let names = available_themes(directory);
+    let count = names.len() as i32;
+    let index = match names.iter().position(|n| n == current) {
+        Some(i) => (i as i32 + direction + count) % count,
+        None => 0
+    };
+    names[index as usize].clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Write;
+
+    // A fresh directory per test, since the tests run in parallel
+    fn theme_directory(name: &str, files: &[(&str, &str)]) -> String {
+        let directory = env::temp_dir().join(format!("polar-theme-test-{}", name));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for &(file, content) in files {
+            File::create(directory.join(file)).unwrap().write_all(content.as_bytes()).unwrap();
+        }
+        directory.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn next_theme_wraps_in_both_directions() {
+        let directory = theme_directory("wrap", &[("dusk.toml", ""), ("zebra.toml", ""), ("notes.txt", "")]);
+        assert_eq!(available_themes(&directory), vec!["classic", "high-contrast", "colourblind", "dusk", "zebra"]);
+        assert_eq!(next_theme("zebra", &directory, 1), "classic");
+        assert_eq!(next_theme("classic", &directory, -1), "zebra");
+        assert_eq!(next_theme("colourblind", &directory, 1), "dusk");
+        assert_eq!(next_theme("dusk", &directory, -1), "colourblind");
+    }
+
+    #[test]
+    fn unknown_current_theme_starts_from_the_first() {
+        let directory = theme_directory("unknown", &[]);
+        assert_eq!(next_theme("missing", &directory, 1), "classic");
+        assert_eq!(next_theme("missing", &directory, -1), "classic");
+    }
+
+    #[test]
+    fn missing_slots_keep_classic_colours() {
+        let directory = theme_directory("slots", &[("sparse.toml", "flare = [0.1, 0.2, 0.3, 1.0]\n")]);
+        let theme = Theme::load(&Path::new(&directory).join("sparse.toml")).unwrap();
+        let classic = Theme::classic();
+        assert_eq!(theme.name, "sparse");
+        assert_eq!(theme.flare, [0.1, 0.2, 0.3, 1.0]);
+        assert_eq!(theme.flare_tail, classic.flare_tail);
+        assert_eq!(theme.frame, classic.frame);
+        assert_eq!(theme.background, classic.background);
+        assert_eq!(theme.hitbox, classic.hitbox);
+    }
+
+    #[test]
+    fn unreadable_theme_files_are_errors() {
+        let directory = theme_directory("errors", &[("broken.toml", "flare = \"red\"\n")]);
+        assert!(Theme::find("broken", &directory).is_err());
+        assert!(Theme::find("absent", &directory).is_err());
+        assert_eq!(Theme::find("classic", &directory), Ok(Theme::classic()));
+    }
+}
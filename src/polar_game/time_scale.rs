@@ -0,0 +1,53 @@
+/*
+Handles the Scaling of Simulation Time (Game Speed and the Death Cam)
+*/
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct TimeScale {
+    game_speed: f64,
+    current: f64,
+    target: f64,
+    ramp_rate: f64,
+}
+
+impl TimeScale {
+    pub fn new(game_speed: f64) -> TimeScale {
+        TimeScale {
+            game_speed: game_speed,
+            current: 1.0,
+            target: 1.0,
+            ramp_rate: 0.0,
+        }
+    }
+
+    // Moves towards the target scale over the given duration of real time
+    pub fn ramp_to(&mut self, target: f64, duration: f64) {
+        self.target = target;
+        self.ramp_rate = if duration > 0.0 { (target - self.current).abs() / duration } else { 0.0 };
+        if duration <= 0.0 {
+            self.current = target;
+        }
+    }
+
+    pub fn update(&mut self, real_step: f64) {
+        if self.current == self.target {
+            return;
+        }
+        let step = self.ramp_rate * real_step;
+        if (self.target - self.current).abs() <= step {
+            self.current = self.target;
+        } else if self.target > self.current {
+            self.current += step;
+        } else {
+            self.current -= step;
+        }
+    }
+
+    pub fn scale(&self, real_step: f64) -> f64 {
+        real_step * self.get_scale()
+    }
+
+    pub fn get_scale(&self) -> f64 {
+        self.current * self.game_speed
+    }
+}
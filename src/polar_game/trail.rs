@@ -0,0 +1,215 @@
+/*
+Fading Motion Trails Behind the Player and Flares
+*/
+
+use super::object::Part;
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrailStyle {
+    // Number of past positions kept, 0 for no trail
+    pub length: usize,
+    // Alpha multiplier applied per step back along the trail
+    pub falloff: f64,
+}
+
+impl Default for TrailStyle {
+    fn default() -> Self {
+        TrailStyle {
+            length: 6,
+            falloff: 0.6,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrailSettings {
+    pub enabled: bool,
+    // Game seconds between samples, so trail length does not depend on the frame rate
+    pub interval: f64,
+    pub player: TrailStyle,
+    pub flare: TrailStyle,
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        TrailSettings {
+            enabled: true,
+            interval: 1.0 / 60.0,
+            player: TrailStyle::default(),
+            flare: TrailStyle { length: 8, falloff: 0.7 },
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Trail {
+    // Newest sample at the front, which is where the object is now and so is not drawn
+    samples: VecDeque<Part>,
+    last_seen: u64,
+}
+
+impl Trail {
+    fn push(&mut self, part: Part, length: usize) {
+        self.samples.push_front(part);
+        self.samples.truncate(length + 1);
+    }
+
+    fn extend_parts(&self, parts: &mut Vec<Part>, style: &TrailStyle, color: [f64; 4]) {
+        let mut alpha = color[3];
+        for sample in self.samples.iter().skip(1) {
+            alpha *= style.falloff;
+            parts.push(sample.with_color([color[0], color[1], color[2], alpha]));
+        }
+    }
+}
+
+pub struct Trails {
+    pub settings: TrailSettings,
+    player: Trail,
+    // Ordered by id so that overlapping trails blend the same way every frame
+    flares: BTreeMap<u64, Trail>,
+    since_sample: f64,
+    sample_count: u64,
+}
+
+impl Trails {
+    pub fn new(settings: TrailSettings) -> Trails {
+        Trails {
+            settings: settings,
+            player: Trail::default(),
+            flares: BTreeMap::new(),
+            since_sample: 0.0,
+            sample_count: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.player.samples.clear();
+        self.flares.clear();
+        self.since_sample = 0.0;
+    }
+
+    // Returns true when this step is due a sample, in which case record_* should be called before end_sample
+    pub fn start_sample(&mut self, t_step: f64) -> bool {
+        if !self.settings.enabled {
+            return false;
+        }
+        self.since_sample += t_step;
+        if self.since_sample < self.settings.interval {
+            return false;
+        }
+        self.since_sample %= self.settings.interval;
+        self.sample_count += 1;
+        true
+    }
+
+    pub fn record_player(&mut self, part: Option<Part>) {
+        match part {
+            Some(part) => self.player.push(part, self.settings.player.length),
+            None => self.player.samples.clear()
+        }
+    }
+
+    pub fn record_flare(&mut self, id: u64, part: Part) {
+        let trail = self.flares.entry(id).or_insert_with(Trail::default);
+        trail.push(part, self.settings.flare.length);
+        trail.last_seen = self.sample_count;
+    }
+
+    // Drops the trails of flares that were not recorded this sample
+    pub fn end_sample(&mut self) {
+        let current = self.sample_count;
+        self.flares.retain(|_, trail| trail.last_seen == current);
+    }
+
+    pub fn extend_player_parts(&self, parts: &mut Vec<Part>, color: [f64; 4]) {
+        if self.settings.enabled {
+            self.player.extend_parts(parts, &self.settings.player, color);
+        }
+    }
+
+    pub fn extend_flare_parts(&self, parts: &mut Vec<Part>, color: [f64; 4]) {
+        if self.settings.enabled {
+            for trail in self.flares.values() {
+                trail.extend_parts(parts, &self.settings.flare, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::object::Point;
+
+    fn part(radial: f64) -> Part {
+        Part { radial: Point { x: radial, y: radial + 0.1 }, angle: Point { x: 0.0, y: 0.1 }, color: [1.0; 4] }
+    }
+
+    fn trails(length: usize, falloff: f64) -> Trails {
+        let style = TrailStyle { length: length, falloff: falloff };
+        Trails::new(TrailSettings { enabled: true, interval: 1.0, player: style, flare: style })
+    }
+
+    #[test]
+    fn trails_keep_their_length_behind_the_current_position() {
+        let mut trails = trails(3, 0.5);
+        for i in 0..10 {
+            assert!(trails.start_sample(1.0));
+            trails.record_player(Some(part(i as f64)));
+            trails.end_sample();
+        }
+        let mut parts = Vec::new();
+        trails.extend_player_parts(&mut parts, [1.0; 4]);
+        let radials: Vec<f64> = parts.iter().map(|p| p.radial.x).collect();
+        assert_eq!(radials, vec![8.0, 7.0, 6.0]);
+    }
+
+    #[test]
+    fn alpha_falls_off_per_step_back() {
+        let mut trails = trails(3, 0.5);
+        for i in 0..4 {
+            trails.start_sample(1.0);
+            trails.record_player(Some(part(i as f64)));
+            trails.end_sample();
+        }
+        let mut parts = Vec::new();
+        trails.extend_player_parts(&mut parts, [0.2, 0.4, 0.6, 0.8]);
+        let alphas: Vec<f64> = parts.iter().map(|p| p.color[3]).collect();
+        assert_eq!(alphas, vec![0.4, 0.2, 0.1]);
+        assert!(parts.iter().all(|p| p.color[0] == 0.2 && p.color[2] == 0.6));
+    }
+
+    #[test]
+    fn flares_missing_from_a_sample_lose_their_trails() {
+        let mut trails = trails(4, 0.5);
+        trails.start_sample(1.0);
+        trails.record_flare(1, part(1.0));
+        trails.record_flare(2, part(2.0));
+        trails.end_sample();
+        trails.start_sample(1.0);
+        trails.record_flare(1, part(1.5));
+        trails.end_sample();
+
+        let mut parts = Vec::new();
+        trails.extend_flare_parts(&mut parts, [1.0; 4]);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].radial.x, 1.0);
+        assert!(!trails.flares.contains_key(&2));
+    }
+
+    #[test]
+    fn disabled_trails_draw_nothing() {
+        let mut trails = trails(3, 0.5);
+        trails.settings.enabled = false;
+        assert!(!trails.start_sample(1.0));
+        trails.record_player(Some(part(0.0)));
+        trails.record_player(Some(part(1.0)));
+        let mut parts = Vec::new();
+        trails.extend_player_parts(&mut parts, [1.0; 4]);
+        assert!(parts.is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+/*
+Switches Between Tunnel and Flat Projection with a Smooth Transition
+*/
+
+use super::object::{Part, Point};
+use super::controls::rising_edge;
+use ::rendering::projection::render_radial;
+use gg::games::view_details::PolarViewDetails;
+
+const TRANSITION_DURATION: f64 = 0.6;
+
+pub struct ViewMode {
+    tunnel: bool,
+    // 0.0 is fully flat and 1.0 fully tunnel
+    blend: f64,
+    unrolled: bool,
+    toggle_lock: bool,
+    unrolled_lock: bool,
+}
+
+impl ViewMode {
+    pub fn new(tunnel: bool) -> ViewMode {
+        ViewMode {
+            tunnel: tunnel,
+            blend: if tunnel { 1.0 } else { 0.0 },
+            unrolled: false,
+            toggle_lock: false,
+            unrolled_lock: false,
+        }
+    }
+
+    pub fn is_tunnel(&self) -> bool {
+        self.tunnel
+    }
+
+    pub fn set_tunnel(&mut self, tunnel: bool) {
+        self.tunnel = tunnel;
+    }
+
+    pub fn is_unrolled(&self) -> bool {
+        self.unrolled
+    }
+
+    // Flips the projection on the first frame the toggle is held, returning true if it changed
+    pub fn update_toggle(&mut self, pressed: bool) -> bool {
+        let flip = rising_edge(pressed, &mut self.toggle_lock);
+        if flip {
+            self.tunnel = !self.tunnel;
+        }
+        flip
+    }
+
+    pub fn update_unrolled_toggle(&mut self, pressed: bool) {
+        if rising_edge(pressed, &mut self.unrolled_lock) {
+            self.unrolled = !self.unrolled;
+        }
+    }
+
+    pub fn update(&mut self, real_step: f64) {
+        let step = real_step / TRANSITION_DURATION;
+        self.blend = if self.tunnel {
+            (self.blend + step).min(1.0)
+        } else {
+            (self.blend - step).max(0.0)
+        };
+    }
+
+    pub fn is_transitioning(&self) -> bool {
+        self.blend > 0.0 && self.blend < 1.0
+    }
+
+    // While transitioning the parts are projected here and the shader is left with an unshifted flat view
+    pub fn get_view(&self, camera: &PolarViewDetails) -> PolarViewDetails {
+        let mut view = camera.clone();
+        if self.is_transitioning() {
+            view.tunnel_mode = false;
+            view.radial_shift = 0.0;
+        }
+        else {
+            view.tunnel_mode = self.tunnel;
+        }
+        view
+    }
+
+    pub fn project_parts(&self, parts: Vec<Part>, camera: &PolarViewDetails) -> Vec<Part> {
+        if !self.is_transitioning() {
+            return parts;
+        }
+        let mut flat = camera.clone();
+        flat.tunnel_mode = false;
+        let mut tunnel = camera.clone();
+        tunnel.tunnel_mode = true;
+        let t = self.blend * self.blend * (3.0 - 2.0 * self.blend);
+        let project = |radial: f64| render_radial(radial, &flat) * (1.0 - t) + render_radial(radial, &tunnel) * t;
+
+        parts.into_iter().map(|p| Part {
+            radial: Point{x: project(p.radial.x), y: project(p.radial.y)},
+            angle: p.angle,
+            color: p.color
+        }).collect()
+    }
+}
@@ -0,0 +1,40 @@
+/*
+Loads Image Assets into Texture Layers for Both Renderers
+*/
+
+use ::rendering::software::png_file::load_png;
+
+// Rows run from the top of the image down, as stored in the file
+#[derive(Clone, Debug)]
+pub struct TextureLayer {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl TextureLayer {
+    // Nearest sample at texture coordinates with t = 0 at the bottom of the image
+    pub fn sample(&self, s: f64, t: f64) -> [f64; 4] {
+        let x = ((s.max(0.0).min(1.0) * self.width as f64) as u32).min(self.width - 1);
+        let y = (((1.0 - t.max(0.0).min(1.0)) * self.height as f64) as u32).min(self.height - 1);
+        let i = ((y * self.width + x) * 4) as usize;
+        let channel = |c: usize| self.rgba[i + c] as f64 / 255.0;
+        [channel(0), channel(1), channel(2), channel(3)]
+    }
+}
+
+// Each path becomes one layer, in order; a texture array needs every layer to be the same size
+pub fn load_texture_layers(paths: &[String]) -> Result<Vec<TextureLayer>, String> {
+    let mut layers: Vec<TextureLayer> = Vec::with_capacity(paths.len());
+    for path in paths.iter() {
+        let (width, height, rgba) = load_png(path).map_err(|e| format!("Failed to load texture {}: {}", path, e))?;
+        if let Some(first) = layers.first() {
+            if (width, height) != (first.width, first.height) {
+                return Err(format!("Texture {} is {}x{} but the first texture is {}x{}",
+                                   path, width, height, first.width, first.height));
+            }
+        }
+        layers.push(TextureLayer { width: width, height: height, rgba: rgba });
+    }
+    Ok(layers)
+}
@@ -1,13 +1,17 @@
 use gg::rendering::Renderer;
 use gg::rendering::primitives::text::{TextBuffer, PlainText};
 use ::rendering::primitives::polar_pixel::{PolarBuffer};
-use ::rendering::{PolarPrimitive, PolarRenderable};
+use ::rendering::primitives::unrolled_pixel::{UnrolledBuffer};
+use ::rendering::{PolarPrimitive, PolarRenderable, Background};
+use ::rendering::post_process::{PostProcessor, PostEffects};
+use ::rendering::assets::TextureLayer;
 use gg::rendering::WindowSpec;
 use gg::rendering::DisplaySettings;
 use gg::rendering::glium_buffer::{GliumBuffer};
 use glium;
 use glium::{Display, Surface, DrawParameters, Depth, DepthTest};
 use glium::texture;
+use glium::texture::texture2d_array::Texture2dArray;
 use glium::glutin::EventsLoop;
 use na;
 use na::Matrix4;
@@ -15,16 +19,22 @@ use num::One;
 use gg::games::view_details;
 use gg::utils::transforms_2d;
 use gg::debug::*;
+use std::rc::Rc;
 
 pub struct GliumRenderer<'a> {
     display: Box<Display>,
     events_loop: Box<EventsLoop>,
     draw_params: DrawParameters<'a>,
     polar_buffer: PolarBuffer,
+    unrolled_buffer: UnrolledBuffer,
     text_processor: TextBuffer<'a, PlainText>,
+    background: Background,
+    post_processor: PostProcessor,
+    post_effects: PostEffects,
     view_details: view_details::ViewDetails,
     display_settings: DisplaySettings,
-    texture_array: texture::srgb_texture2d_array::SrgbTexture2dArray
+    // Colours throughout are used as given, so the layers are not treated as sRGB
+    texture_array: Rc<Texture2dArray>
 }
 
 impl<'a> GliumRenderer<'a> {
@@ -39,15 +49,20 @@ impl<'a> GliumRenderer<'a> {
             ..Default::default()
         };
 
+        let texture_array = Rc::new(Texture2dArray::empty(&display, 1, 1, 1).unwrap());
         GliumRenderer {
             display: Box::new(display.clone()),
             events_loop: Box::new(events_loop),
             draw_params: draw_params,
-            polar_buffer: PolarBuffer::new(&display),
+            polar_buffer: PolarBuffer::new(&display, texture_array.clone()),
+            unrolled_buffer: UnrolledBuffer::new(&display),
             text_processor: TextBuffer::new(&display, settings),
+            background: Background::default(),
+            post_processor: PostProcessor::new(&display),
+            post_effects: PostEffects::default(),
             view_details: view_details::ViewDetails::TwoDim(view_details::ViewDetails2D::default()),
             display_settings: settings,
-            texture_array: texture::srgb_texture2d_array::SrgbTexture2dArray::empty(&display, 1024, 1024, 1).unwrap()
+            texture_array: texture_array
         }
     }
 
@@ -59,10 +74,30 @@ impl<'a> GliumRenderer<'a> {
         self.display.rebuild(window, context, &self.events_loop).unwrap();
     }
 
+    // Fills the texture array sampled by textured polar pixels, one layer per image
+    pub fn with_textures<'b>(&'b mut self, layers: &[TextureLayer]) -> &'b mut Self {
+        if layers.is_empty() {
+            return self;
+        }
+        let images: Vec<texture::RawImage2d<u8>> = layers.iter()
+            .map(|l| texture::RawImage2d::from_raw_rgba_reversed(&l.rgba, (l.width, l.height)))
+            .collect();
+        match Texture2dArray::new(&*self.display, images) {
+            Ok(array) => {
+                self.texture_array = Rc::new(array);
+                self.polar_buffer.set_textures(self.texture_array.clone());
+            },
+            Err(e) => debug(&format!("Failed to create the texture array: {:?}", e))
+        }
+        self
+    }
+
     fn reset_buffers(&mut self) {
         let display = &self.display;
-        self.polar_buffer = PolarBuffer::new(display);
+        self.polar_buffer = PolarBuffer::new(display, self.texture_array.clone());
+        self.unrolled_buffer = UnrolledBuffer::new(display);
         self.text_processor = TextBuffer::new(display, self.display_settings);
+        self.post_processor = PostProcessor::new(display);
     }
 
     fn build_display_and_events_loop(settings: DisplaySettings) -> (Display, EventsLoop) {
@@ -93,6 +128,7 @@ impl<'a> GliumRenderer<'a> {
 
     fn flush_buffers(&mut self) {
         self.polar_buffer.flush_buffer();
+        self.unrolled_buffer.flush_buffer();
         self.text_processor.flush_buffer();
     }
     
@@ -125,6 +161,11 @@ impl<'a> Renderer for GliumRenderer<'a> {
                 match primitive {
                         PolarPrimitive::Text(text) => self.text_processor.load_renderable(text),
                         PolarPrimitive::PolarPix(polar) => self.polar_buffer.load_renderable(polar),
+                        PolarPrimitive::PolarBatch(pixels) => self.polar_buffer.load_batch(pixels),
+                        PolarPrimitive::StaticPolar(pixels) => self.polar_buffer.load_static(&pixels),
+                        PolarPrimitive::Unrolled(unrolled) => self.unrolled_buffer.load_renderable(unrolled),
+                        PolarPrimitive::Background(background) => self.background = background,
+                        PolarPrimitive::PostEffects(effects) => self.post_effects = effects,
                 }
             }
         }
@@ -134,7 +175,8 @@ impl<'a> Renderer for GliumRenderer<'a> {
     fn render(&mut self) {
         debug_clock_start("Render::glium_render");
         let mut target = self.display.draw();
-        target.clear_color(0.0, 0.0, 0.0, 1.0);
+        let color = self.background.color;
+        target.clear_color(color[0] as f32, color[1] as f32, color[2] as f32, color[3] as f32);
         target.clear_depth(1.0);
 
         let (width, height) = target.get_dimensions();
@@ -146,10 +188,13 @@ impl<'a> Renderer for GliumRenderer<'a> {
                 screen_height: height,
                 aspect_ratio: aspect_ratio as f32,
                 world_view: GliumRenderer::create_worldview_mat(self.view_details, aspect_ratio),
-                tex: &self.texture_array
+                tex: &*self.texture_array
             };
             
             self.polar_buffer.draw_at_target(&mut target, &self.display, self.view_details, &self.draw_params, &uniforms);
+            self.unrolled_buffer.draw_at_target(&mut target, &self.display, self.view_details, &self.draw_params, &uniforms);
+            // Text goes on after the effects so that the HUD stays sharp
+            self.post_processor.apply(&mut target, &self.display, &self.post_effects);
             self.text_processor.draw_at_target(&mut target, &self.display, self.view_details, &self.draw_params, &uniforms);
             
             target.finish().unwrap();
@@ -1,9 +1,14 @@
 pub mod primitives;
 pub mod renderables;
 pub mod glium_renderer;
+pub mod projection;
+pub mod software;
+pub mod post_process;
+pub mod assets;
 
-pub use ::rendering::primitives::{PolarPixel, PolarPrimitive};
+pub use ::rendering::primitives::{PolarPixel, PixelFill, StaticPixels, PolarPixelBatch, UnrolledPixel, Background, PolarPrimitive};
 pub use ::rendering::glium_renderer::{GliumRenderer};
+pub use ::rendering::software::SoftwareRenderer;
 pub use ::rendering::renderables::{PolarRenderable};
 
 use gg::rendering::renderables::{Renderable, Line, LineShape, Arrow, Circle, BoxBorder, Annulus, AnnularSegment};
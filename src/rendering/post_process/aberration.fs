@@ -0,0 +1,19 @@
+/*
+Chromatic Aberration Fragment Shader
+ */
+
+#version 410 core
+
+in vec2 uv;
+
+uniform sampler2D source;
+uniform float strength;
+
+out vec4 color;
+
+void main()
+{
+  // Red and blue are pulled apart along the line to the screen centre
+  vec2 offset = (uv - 0.5f) * 0.02f * strength;
+  color = vec4(texture(source, uv + offset).r, texture(source, uv).g, texture(source, uv - offset).b, 1.0f);
+}
@@ -0,0 +1,18 @@
+/*
+Bloom Fragment Shader, Adding the Blurred Bright Pass Back onto the Scene
+ */
+
+#version 410 core
+
+in vec2 uv;
+
+uniform sampler2D source;
+uniform sampler2D bloom;
+uniform float strength;
+
+out vec4 color;
+
+void main()
+{
+  color = vec4(texture(source, uv).rgb + texture(bloom, uv).rgb * strength, 1.0f);
+}
@@ -0,0 +1,26 @@
+/*
+Separable Gaussian Blur Fragment Shader
+ */
+
+#version 410 core
+
+in vec2 uv;
+
+uniform sampler2D source;
+uniform vec2 direction;
+
+out vec4 color;
+
+const float weights[5] = float[](0.227027f, 0.1945946f, 0.1216216f, 0.054054f, 0.016216f);
+
+void main()
+{
+  vec2 texel = direction / vec2(textureSize(source, 0));
+  vec3 sum = texture(source, uv).rgb * weights[0];
+  for (int i = 1; i < 5; i++)
+  {
+    sum += texture(source, uv + texel * float(i)).rgb * weights[i];
+    sum += texture(source, uv - texel * float(i)).rgb * weights[i];
+  }
+  color = vec4(sum, 1.0f);
+}
@@ -0,0 +1,21 @@
+/*
+Bloom Bright Pass Fragment Shader
+ */
+
+#version 410 core
+
+in vec2 uv;
+
+uniform sampler2D scene;
+uniform float threshold;
+
+out vec4 color;
+
+void main()
+{
+  vec3 scene_color = texture(scene, uv).rgb;
+  float luminance = dot(scene_color, vec3(0.2126f, 0.7152f, 0.0722f));
+  // A soft knee so that objects near the threshold do not flicker in and out of the glow
+  float weight = smoothstep(threshold, threshold + 0.2f, luminance);
+  color = vec4(scene_color * weight, 1.0f);
+}
@@ -0,0 +1,14 @@
+/*
+Full Screen Quad Vertex Shader for Post-Processing
+ */
+
+#version 410 core
+in vec2 position;
+
+out vec2 uv;
+
+void main()
+{
+  uv = position * 0.5f + 0.5f;
+  gl_Position = vec4(position, 0.0f, 1.0f);
+}
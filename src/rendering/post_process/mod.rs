@@ -0,0 +1,254 @@
+/*
+Offscreen Post-Processing Chain Run After the Playfield Is Drawn
+*/
+
+use glium;
+use glium::{Display, Surface, Frame, Program, VertexBuffer, BlitTarget};
+use glium::texture::Texture2d;
+use glium::index::{NoIndices, PrimitiveType};
+use glium::uniforms::{Sampler, SamplerWrapFunction, MagnifySamplerFilter, MinifySamplerFilter};
+
+const VIGNETTE_STRENGTH: f32 = 0.6;
+const SCANLINE_STRENGTH: f32 = 0.25;
+const BLOOM_BLUR_PASSES: usize = 2;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessSettings {
+    // Turns every pass off, for players who find glow and flashing uncomfortable
+    pub reduced_effects: bool,
+    pub bloom: bool,
+    // Scene luminance where objects start to glow
+    pub bloom_threshold: f64,
+    pub bloom_strength: f64,
+    pub vignette: bool,
+    pub chromatic_aberration: bool,
+    pub scanlines: bool,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        PostProcessSettings {
+            reduced_effects: false,
+            bloom: true,
+            bloom_threshold: 0.3,
+            bloom_strength: 1.2,
+            vignette: true,
+            chromatic_aberration: true,
+            scanlines: false,
+        }
+    }
+}
+
+// Sent by the game every frame, so settings changes and death effects apply straight away
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PostEffects {
+    pub settings: PostProcessSettings,
+    // 0.0 for none, up to 1.0 at the moment of death
+    pub aberration: f64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pass {
+    Bloom { threshold: f32, strength: f32 },
+    ChromaticAberration(f32),
+    Vignette(f32),
+    Scanlines(f32),
+}
+
+impl PostEffects {
+    // The chain in the order it is applied; empty when nothing needs the offscreen copy
+    pub fn get_passes(&self) -> Vec<Pass> {
+        let settings = &self.settings;
+        let mut passes = Vec::new();
+        if settings.reduced_effects {
+            return passes;
+        }
+        if settings.bloom && settings.bloom_strength > 0.0 {
+            passes.push(Pass::Bloom { threshold: settings.bloom_threshold as f32, strength: settings.bloom_strength as f32 });
+        }
+        if settings.chromatic_aberration && self.aberration > 0.0 {
+            passes.push(Pass::ChromaticAberration(self.aberration as f32));
+        }
+        if settings.vignette {
+            passes.push(Pass::Vignette(VIGNETTE_STRENGTH));
+        }
+        if settings.scanlines {
+            passes.push(Pass::Scanlines(SCANLINE_STRENGTH));
+        }
+        passes
+    }
+}
+
+#[derive(Copy, Clone)]
+struct QuadVertex {
+    position: [f32; 2]
+}
+
+implement_vertex!(QuadVertex, position);
+
+struct Targets {
+    size: (u32, u32),
+    // Passes read from one and write to the other, swapping after each pass
+    scene: [Texture2d; 2],
+    // Half resolution, since the blur hides the loss and it quarters the cost
+    bloom: [Texture2d; 2],
+}
+
+impl Targets {
+    fn new(display: &Display, size: (u32, u32)) -> Targets {
+        let full = || Texture2d::empty(display, size.0, size.1).unwrap();
+        let half = || Texture2d::empty(display, (size.0 / 2).max(1), (size.1 / 2).max(1)).unwrap();
+        Targets {
+            size: size,
+            scene: [full(), full()],
+            bloom: [half(), half()],
+        }
+    }
+}
+
+pub struct PostProcessor {
+    quad: VertexBuffer<QuadVertex>,
+    bright_program: Program,
+    blur_program: Program,
+    bloom_program: Program,
+    aberration_program: Program,
+    vignette_program: Program,
+    scanlines_program: Program,
+    targets: Option<Targets>,
+}
+
+impl PostProcessor {
+    pub fn new(display: &Display) -> PostProcessor {
+        let vertices = [
+            QuadVertex { position: [-1.0, -1.0] },
+            QuadVertex { position: [1.0, -1.0] },
+            QuadVertex { position: [-1.0, 1.0] },
+            QuadVertex { position: [1.0, 1.0] },
+        ];
+        let program = |fragment: &str| Program::from_source(display, include_str!("fullscreen.vs"), fragment, None).unwrap();
+        PostProcessor {
+            quad: VertexBuffer::new(display, &vertices).unwrap(),
+            bright_program: program(include_str!("bright.fs")),
+            blur_program: program(include_str!("blur.fs")),
+            bloom_program: program(include_str!("bloom.fs")),
+            aberration_program: program(include_str!("aberration.fs")),
+            vignette_program: program(include_str!("vignette.fs")),
+            scanlines_program: program(include_str!("scanlines.fs")),
+            targets: None,
+        }
+    }
+
+    // The glium buffers can only draw to the window's frame, so the finished playfield is copied offscreen,
+    // which also resolves any multisampling, and the last pass writes back over it
+    pub fn apply(&mut self, target: &mut Frame, display: &Display, effects: &PostEffects) {
+        let passes = effects.get_passes();
+        if passes.is_empty() {
+            return;
+        }
+        let size = target.get_dimensions();
+        if self.targets.as_ref().map_or(true, |t| t.size != size) {
+            self.targets = Some(Targets::new(display, size));
+        }
+        let targets = self.targets.as_ref().unwrap();
+
+        let whole = BlitTarget { left: 0, bottom: 0, width: size.0 as i32, height: size.1 as i32 };
+        target.blit_whole_color_to(&targets.scene[0].as_surface(), &whole, MagnifySamplerFilter::Nearest);
+
+        let mut source = 0;
+        for (i, pass) in passes.iter().enumerate() {
+            if let Pass::Bloom { threshold, .. } = *pass {
+                self.build_bloom(targets, source, threshold);
+            }
+            if i + 1 == passes.len() {
+                self.draw_pass(target, targets, source, pass);
+            }
+            else {
+                let mut surface = targets.scene[1 - source].as_surface();
+                self.draw_pass(&mut surface, targets, source, pass);
+                source = 1 - source;
+            }
+        }
+    }
+
+    // Leaves the blurred bright parts of the scene in bloom[0]
+    fn build_bloom(&self, targets: &Targets, source: usize, threshold: f32) {
+        let uniforms = uniform! {
+            scene: clamped(&targets.scene[source]),
+            threshold: threshold
+        };
+        self.draw_quad(&mut targets.bloom[0].as_surface(), &self.bright_program, &uniforms);
+        for _ in 0..BLOOM_BLUR_PASSES {
+            for &(from, direction) in [(0, [1.0f32, 0.0]), (1, [0.0f32, 1.0])].iter() {
+                let uniforms = uniform! {
+                    source: clamped(&targets.bloom[from]),
+                    direction: direction
+                };
+                self.draw_quad(&mut targets.bloom[1 - from].as_surface(), &self.blur_program, &uniforms);
+            }
+        }
+    }
+
+    fn draw_pass<S: Surface>(&self, surface: &mut S, targets: &Targets, source: usize, pass: &Pass) {
+        let (program, strength) = match *pass {
+            Pass::Bloom { strength, .. } => (&self.bloom_program, strength),
+            Pass::ChromaticAberration(strength) => (&self.aberration_program, strength),
+            Pass::Vignette(strength) => (&self.vignette_program, strength),
+            Pass::Scanlines(strength) => (&self.scanlines_program, strength),
+        };
+        let uniforms = uniform! {
+            source: clamped(&targets.scene[source]),
+            bloom: clamped(&targets.bloom[0]),
+            strength: strength
+        };
+        self.draw_quad(surface, program, &uniforms);
+    }
+
+    fn draw_quad<S: Surface, U: glium::uniforms::Uniforms>(&self, surface: &mut S, program: &Program, uniforms: &U) {
+        surface.draw(&self.quad, &NoIndices(PrimitiveType::TriangleStrip), program, uniforms, &Default::default()).unwrap();
+    }
+}
+
+fn clamped(texture: &Texture2d) -> Sampler<Texture2d> {
+    Sampler::new(texture)
+        .wrap_function(SamplerWrapFunction::Clamp)
+        .minify_filter(MinifySamplerFilter::Linear)
+        .magnify_filter(MagnifySamplerFilter::Linear)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effects(settings: PostProcessSettings, aberration: f64) -> PostEffects {
+        PostEffects { settings: settings, aberration: aberration }
+    }
+
+    #[test]
+    fn reduced_effects_empties_the_chain() {
+        let settings = PostProcessSettings { reduced_effects: true, scanlines: true, ..Default::default() };
+        assert!(effects(settings, 1.0).get_passes().is_empty());
+    }
+
+    #[test]
+    fn aberration_only_runs_after_a_death() {
+        let settings = PostProcessSettings::default();
+        assert!(!effects(settings, 0.0).get_passes().iter().any(|p| match *p { Pass::ChromaticAberration(_) => true, _ => false }));
+        assert!(effects(settings, 0.5).get_passes().contains(&Pass::ChromaticAberration(0.5)));
+        let disabled = PostProcessSettings { chromatic_aberration: false, ..Default::default() };
+        assert!(!effects(disabled, 0.5).get_passes().contains(&Pass::ChromaticAberration(0.5)));
+    }
+
+    #[test]
+    fn passes_follow_their_toggles_in_order() {
+        let settings = PostProcessSettings { scanlines: true, ..Default::default() };
+        assert_eq!(effects(settings, 0.25).get_passes(), vec![
+            Pass::Bloom { threshold: 0.3, strength: 1.2 },
+            Pass::ChromaticAberration(0.25),
+            Pass::Vignette(VIGNETTE_STRENGTH),
+            Pass::Scanlines(SCANLINE_STRENGTH),
+        ]);
+        let none = PostProcessSettings { bloom: false, vignette: false, ..Default::default() };
+        assert!(effects(none, 0.0).get_passes().is_empty());
+    }
+}
@@ -0,0 +1,18 @@
+/*
+CRT Scanline Fragment Shader
+ */
+
+#version 410 core
+
+in vec2 uv;
+
+uniform sampler2D source;
+uniform float strength;
+
+out vec4 color;
+
+void main()
+{
+  float row = mod(floor(gl_FragCoord.y), 2.0f);
+  color = vec4(texture(source, uv).rgb * (1.0f - strength * (1.0f - row)), 1.0f);
+}
@@ -0,0 +1,19 @@
+/*
+Vignette Fragment Shader
+ */
+
+#version 410 core
+
+in vec2 uv;
+
+uniform sampler2D source;
+uniform float strength;
+
+out vec4 color;
+
+void main()
+{
+  vec2 from_center = uv - 0.5f;
+  float edge = dot(from_center, from_center) * 2.0f;
+  color = vec4(texture(source, uv).rgb * (1.0f - strength * smoothstep(0.1f, 0.5f, edge)), 1.0f);
+}
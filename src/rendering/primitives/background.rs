@@ -0,0 +1,11 @@
+// The colour the frame is cleared to before anything is drawn; the last one loaded is kept
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Background {
+    pub color: [f64; 4]
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background { color: [0.0, 0.0, 0.0, 1.0] }
+    }
+}
@@ -1,9 +1,19 @@
 pub mod polar_pixel;
-pub use self::polar_pixel::PolarPixel;
+pub mod unrolled_pixel;
+pub mod background;
+pub use self::polar_pixel::{PolarPixel, PixelFill, StaticPixels, PolarPixelBatch};
+pub use self::unrolled_pixel::UnrolledPixel;
+pub use self::background::Background;
+use ::rendering::post_process::PostEffects;
 use gg::rendering::PlainText;
 
 #[derive(Clone)]
 pub enum PolarPrimitive {
     PolarPix(PolarPixel),
+    PolarBatch(Vec<PolarPixel>),
+    StaticPolar(StaticPixels),
+    Unrolled(UnrolledPixel),
     Text(PlainText),
+    Background(Background),
+    PostEffects(PostEffects),
 }
\ No newline at end of file
@@ -1,13 +1,51 @@
 use gg::rendering::render_by_shaders::GliumStandardPrimitive;
 use gg::rendering::shaders::Shaders;
+use std::rc::Rc;
 mod polar_buffer;
+pub mod staging;
 pub use self::polar_buffer::PolarBuffer;
 
 #[derive(Copy, Clone)]
 pub struct PolarPixel {
     pub radial: [f64; 2],
     pub angle: [f64; 2],
-    pub color: [f64; 4]
+    pub color: [f64; 4],
+    pub fill: PixelFill
+}
+
+// The gradient runs from the pixel's colour to the given end colour; textures are tinted by the pixel's colour
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PixelFill {
+    Flat,
+    // Inner edge to outer edge
+    RadialGradient([f64; 4]),
+    // A layer of the renderer's texture array, with the angle across and the radius up the image
+    Textured(u32),
+}
+
+impl Default for PixelFill {
+    fn default() -> Self {
+        PixelFill::Flat
+    }
+}
+
+impl PolarPixel {
+    pub fn with_fill(self, fill: PixelFill) -> PolarPixel {
+        PolarPixel { fill: fill, ..self }
+    }
+}
+
+// Geometry that rarely changes; renderers keep it uploaded until a pixel set with a new key arrives
+#[derive(Clone)]
+pub struct StaticPixels {
+    pub key: u64,
+    pub pixels: Rc<Vec<PolarPixel>>
+}
+
+// Many pixels loaded as one renderable, saving a box per part
+#[derive(Clone, Default)]
+pub struct PolarPixelBatch {
+    pub pixels: Vec<PolarPixel>
 }
 
 impl GliumStandardPrimitive for PolarPixel {
@@ -23,21 +61,31 @@ impl GliumStandardPrimitive for PolarPixel {
     fn get_vertex(self) -> Vec<Self::Vertex> { vec![self.clone().into()] }
 }
 
-implement_vertex!(PolarPixelVertex, radial, angle, color);
+implement_vertex!(PolarPixelVertex, radial, angle, color, color_end, fill);
 
 #[derive(Copy, Clone, Debug)]
 pub struct PolarPixelVertex {
     pub radial: [f64; 2],
     pub angle: [f64; 2],
-    pub color: [f64; 4]
+    pub color: [f64; 4],
+    pub color_end: [f64; 4],
+    // The fill mode, matching the order of PixelFill, and the texture layer
+    pub fill: [i32; 2]
 }
 
 impl From<PolarPixel> for PolarPixelVertex {
     fn from(pol: PolarPixel) -> Self {
+        let (color_end, fill) = match pol.fill {
+            PixelFill::Flat => (pol.color, [0, 0]),
+            PixelFill::RadialGradient(end) => (end, [1, 0]),
+            PixelFill::Textured(layer) => (pol.color, [2, layer as i32]),
+        };
         PolarPixelVertex {
             radial: pol.radial,
             angle: pol.angle,
-            color: pol.color
+            color: pol.color,
+            color_end: color_end,
+            fill: fill
         }
     }
 }
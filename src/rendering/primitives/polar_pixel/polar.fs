@@ -7,14 +7,18 @@ Standard Polar Fragment Shader
 in vec2 radial_geom;
 in vec2 angle_geom;
 in vec4 color_geom;
+in vec4 color_end_geom;
+flat in ivec2 fill_geom;
 in vec2 emit_vertex;
 
 uniform vec2 center;
 uniform float aspect_ratio;
+uniform sampler2DArray tex;
 
 out vec4 color;
 
 bool angleCompare(in float a, in vec2 range);
+vec4 fillColor(in float radius, in float angle);
 
 void main()
 {
@@ -26,14 +30,15 @@ void main()
   bool radialOverlap = fragRadius >= radial_square.x && fragRadius <= radial_square.y;
 
   bool angleOverlap = true;
+  float angle = 0.0f;
   if(radialOverlap)
     {
-      float angle = atan(fragCoord.y, fragCoord.x);
+      angle = atan(fragCoord.y, fragCoord.x);
       angle = degrees(angle) / 360.0f;
       angleOverlap = angleCompare(angle, angle_geom);
     }
    if(angleOverlap && radialOverlap)
-     color = color_geom;
+     color = fillColor(sqrt(fragRadius), angle);
    else
      color = vec4(0.0f, 0.0f, 0.0f, 0.0f);
 }
@@ -60,3 +65,22 @@ bool angleCompare(in float ang, in vec2 range)
       return ang >= range.x ||  ang <= range.y;
     }  
 }
+
+// Polar UV: s runs from the start to the end angle and t from the inner to the outer edge
+vec4 fillColor(in float radius, in float angle)
+{
+  if (fill_geom.x == 0)
+    {
+      return color_geom;
+    }
+  float t = clamp((radius - radial_geom.x) / max(radial_geom.y - radial_geom.x, 0.00001f), 0.0f, 1.0f);
+  float s = clamp(fract(angle - angle_geom.x) / max(angle_geom.y - angle_geom.x, 0.00001f), 0.0f, 1.0f);
+  if (fill_geom.x == 1)
+    {
+      return mix(color_geom, color_end_geom, t);
+    }
+  else
+    {
+      return texture(tex, vec3(s, t, float(fill_geom.y))) * color_geom;
+    }
+}
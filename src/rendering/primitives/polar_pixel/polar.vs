@@ -6,6 +6,8 @@ Standard Polar Vertex Shader
 in vec2 radial;
 in vec2 angle;
 in vec4 color;
+in vec4 color_end;
+in ivec2 fill;
 
 uniform float radial_shift;
 uniform float rotation_angle;
@@ -16,6 +18,8 @@ uniform float length_total;
 out vec2 radial_vertex;
 out vec2 angle_vertex;
 out vec4 color_vertex;
+out vec4 color_end_vertex;
+flat out ivec2 fill_vertex;
 
 float render_radial(in float polar);
 
@@ -32,6 +36,8 @@ void main()
   }
   angle_vertex = vec2(angle.x - rotation_angle, angle.y - rotation_angle);
   color_vertex = color;
+  color_end_vertex = color_end;
+  fill_vertex = fill;
 
   gl_Position = vec4(0.0f, 0.0f, 0.0f, 1.0f);
 }
@@ -2,7 +2,8 @@ use na::{Vector2, Vector4, Matrix2};
 use glium;
 use glium::{Display, Surface, Frame, DrawParameters, Blend};
 use glium::index::PrimitiveType;
-use super::{PolarPixel, PolarPixelVertex};
+use super::{PolarPixel, PolarPixelVertex, StaticPixels};
+use super::staging::{PolarStaging, StreamUpload};
 use gg::rendering;
 use gg::rendering::shaders;
 use gg::rendering::glium_buffer::GliumBuffer;
@@ -10,11 +11,18 @@ use gg::rendering::render_by_shaders::GliumStandardPrimitive;
 use gg::games::view_details;
 use gg::games::view_details::{ViewDetails, PolarViewDetails};
 use gg::rendering::shaders::make_program_from_shaders;
+use glium::texture::texture2d_array::Texture2dArray;
+use std::rc::Rc;
 
+// Static geometry sits in an immutable buffer until it changes, and everything else is streamed
+// into one dynamic buffer that is only reallocated when it runs out of room
 pub struct PolarBuffer {
-    vertices: Vec<PolarPixelVertex>,
+    staging: PolarStaging,
+    static_buffer: Option<glium::VertexBuffer<PolarPixelVertex>>,
+    stream_buffer: Option<glium::VertexBuffer<PolarPixelVertex>>,
     program: glium::Program,
-    primitive_type: PrimitiveType
+    primitive_type: PrimitiveType,
+    textures: Rc<Texture2dArray>
 }
 
 impl GliumBuffer<PolarPixel> for PolarBuffer {
@@ -26,13 +34,28 @@ impl GliumBuffer<PolarPixel> for PolarBuffer {
         _: &DrawParameters,
         _: &Unif,
     ) {
-        if !self.vertices.is_empty() {
+        if let Some(vertices) = self.staging.take_static_upload() {
+            self.static_buffer = Some(glium::VertexBuffer::immutable(display, &vertices).unwrap());
+        }
+        let stream_len = match self.staging.plan_stream() {
+            StreamUpload::Nothing => 0,
+            StreamUpload::Write(len) => len,
+            StreamUpload::Grow(capacity) => {
+                self.stream_buffer = Some(glium::VertexBuffer::empty_dynamic(display, capacity).unwrap());
+                self.staging.get_stream_vertices().len()
+            }
+        };
+        if stream_len > 0 {
+            if let Some(ref stream_buffer) = self.stream_buffer {
+                stream_buffer.slice(0..stream_len).unwrap().write(self.staging.get_stream_vertices());
+            }
+        }
+
+        if self.staging.has_static() || stream_len > 0 {
             let polar_view: PolarViewDetails = match view_details {
                 ViewDetails::Polar(pol_view) => pol_view,
                 _ => panic!("Must use PolarViewDetails with polar pixel rendering elements!")
             };
-            
-            let vertex_buffer = glium::VertexBuffer::new(display, &self.vertices).unwrap();
 
             let (width, height) = target.get_dimensions();
             let aspect_ratio = width as f64 / height as f64;
@@ -43,35 +66,64 @@ impl GliumBuffer<PolarPixel> for PolarBuffer {
                 aspect_ratio: aspect_ratio as f32,
                 tunnel_mode: polar_view.tunnel_mode,
                 length_total: polar_view.length_total as f32,
-                length_circle: polar_view.length_circle as f32
+                length_circle: polar_view.length_circle as f32,
+                tex: &*self.textures
             };
 
             let mut draw_params =  glium::draw_parameters::DrawParameters::default();
             draw_params.blend = Blend::alpha_blending();
             
-            target.draw(&vertex_buffer,
-                        &glium::index::NoIndices(self.primitive_type),
-                        &self.program,
-                        &uniforms,
-                        &draw_params).unwrap();
+            if self.staging.has_static() {
+                if let Some(ref static_buffer) = self.static_buffer {
+                    target.draw(static_buffer,
+                                &glium::index::NoIndices(self.primitive_type),
+                                &self.program,
+                                &uniforms,
+                                &draw_params).unwrap();
+                }
+            }
+            if stream_len > 0 {
+                if let Some(ref stream_buffer) = self.stream_buffer {
+                    target.draw(stream_buffer.slice(0..stream_len).unwrap(),
+                                &glium::index::NoIndices(self.primitive_type),
+                                &self.program,
+                                &uniforms,
+                                &draw_params).unwrap();
+                }
+            }
         }
     }
 
     fn flush_buffer(&mut self) {
-        self.vertices = Vec::new();
+        self.staging.clear();
     }
 
     fn get_vertices(&mut self) -> &mut Vec<PolarPixelVertex> {
-        &mut self.vertices
+        self.staging.get_vertices()
     }
 }
 
 impl PolarBuffer {
-    pub fn new(display: &Display) -> Self {
+    pub fn new(display: &Display, textures: Rc<Texture2dArray>) -> Self {
         PolarBuffer {
-            vertices: Vec::new(),
+            staging: PolarStaging::new(),
+            static_buffer: None,
+            stream_buffer: None,
             program: make_program_from_shaders(PolarPixel::get_shaders(), display),
             primitive_type: PolarPixel::get_primitive_type(),
+            textures: textures,
         }
     }
+
+    pub fn set_textures(&mut self, textures: Rc<Texture2dArray>) {
+        self.textures = textures;
+    }
+
+    pub fn load_batch(&mut self, pixels: Vec<PolarPixel>) {
+        self.staging.extend(pixels);
+    }
+
+    pub fn load_static(&mut self, pixels: &StaticPixels) {
+        self.staging.load_static(pixels);
+    }
 }
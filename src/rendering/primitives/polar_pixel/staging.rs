@@ -0,0 +1,161 @@
+/*
+CPU-Side Bookkeeping for the Persistent Polar Vertex Buffers, Independent of the GPU
+*/
+
+use super::{PolarPixel, PolarPixelVertex, StaticPixels};
+use std::rc::Rc;
+
+const MIN_STREAM_CAPACITY: usize = 256;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StreamUpload {
+    Nothing,
+    // Write this many vertices into the existing stream buffer
+    Write(usize),
+    // Reallocate the stream buffer with this capacity before writing
+    Grow(usize),
+}
+
+pub struct PolarStaging {
+    vertices: Vec<PolarPixelVertex>,
+    stream_capacity: usize,
+    static_key: Option<u64>,
+    pending_static: Option<Rc<Vec<PolarPixel>>>,
+    static_in_frame: bool,
+}
+
+impl PolarStaging {
+    pub fn new() -> PolarStaging {
+        PolarStaging {
+            vertices: Vec::new(),
+            stream_capacity: 0,
+            static_key: None,
+            pending_static: None,
+            static_in_frame: false,
+        }
+    }
+
+    pub fn get_vertices(&mut self) -> &mut Vec<PolarPixelVertex> {
+        &mut self.vertices
+    }
+
+    pub fn get_stream_vertices(&self) -> &[PolarPixelVertex] {
+        &self.vertices
+    }
+
+    pub fn extend(&mut self, pixels: Vec<PolarPixel>) {
+        self.vertices.extend(pixels.into_iter().map(PolarPixelVertex::from));
+    }
+
+    // Static geometry is only handed on for upload when its key changes
+    pub fn load_static(&mut self, pixels: &StaticPixels) {
+        self.static_in_frame = true;
+        if self.static_key != Some(pixels.key) {
+            self.static_key = Some(pixels.key);
+            self.pending_static = Some(pixels.pixels.clone());
+        }
+    }
+
+    pub fn take_static_upload(&mut self) -> Option<Vec<PolarPixelVertex>> {
+        self.pending_static.take().map(|pixels| pixels.iter().map(|p| PolarPixelVertex::from(*p)).collect())
+    }
+
+    pub fn has_static(&self) -> bool {
+        self.static_in_frame
+    }
+
+    // Capacities grow in powers of two so that a steady scene stops reallocating after a few frames
+    pub fn plan_stream(&mut self) -> StreamUpload {
+        let len = self.vertices.len();
+        if len == 0 {
+            StreamUpload::Nothing
+        }
+        else if len <= self.stream_capacity {
+            StreamUpload::Write(len)
+        }
+        else {
+            self.stream_capacity = len.next_power_of_two().max(MIN_STREAM_CAPACITY);
+            StreamUpload::Grow(self.stream_capacity)
+        }
+    }
+
+    // Keeps the staging allocation for the next frame
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.static_in_frame = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::PixelFill;
+
+    fn pixels(count: usize) -> Vec<PolarPixel> {
+        vec![PolarPixel { radial: [1.0, 2.0], angle: [0.0, 0.1], color: [1.0; 4], fill: PixelFill::Flat }; count]
+    }
+
+    fn static_pixels(key: u64, count: usize) -> StaticPixels {
+        StaticPixels { key: key, pixels: Rc::new(pixels(count)) }
+    }
+
+    #[test]
+    fn stream_plans_nothing_then_grows_then_writes() {
+        let mut staging = PolarStaging::new();
+        assert_eq!(staging.plan_stream(), StreamUpload::Nothing);
+
+        staging.extend(pixels(10));
+        assert_eq!(staging.plan_stream(), StreamUpload::Grow(MIN_STREAM_CAPACITY));
+        staging.clear();
+
+        staging.extend(pixels(MIN_STREAM_CAPACITY));
+        assert_eq!(staging.plan_stream(), StreamUpload::Write(MIN_STREAM_CAPACITY));
+        staging.clear();
+        assert_eq!(staging.plan_stream(), StreamUpload::Nothing);
+    }
+
+    #[test]
+    fn stream_grows_to_powers_of_two_and_never_shrinks() {
+        let mut staging = PolarStaging::new();
+        staging.extend(pixels(MIN_STREAM_CAPACITY + 1));
+        assert_eq!(staging.plan_stream(), StreamUpload::Grow(2 * MIN_STREAM_CAPACITY));
+        staging.clear();
+
+        staging.extend(pixels(1000));
+        assert_eq!(staging.plan_stream(), StreamUpload::Grow(1024));
+        staging.clear();
+
+        staging.extend(pixels(3));
+        assert_eq!(staging.plan_stream(), StreamUpload::Write(3));
+        staging.clear();
+        staging.extend(pixels(1024));
+        assert_eq!(staging.plan_stream(), StreamUpload::Write(1024));
+    }
+
+    #[test]
+    fn static_pixels_upload_once_per_key() {
+        let mut staging = PolarStaging::new();
+        staging.load_static(&static_pixels(1, 4));
+        assert_eq!(staging.take_static_upload().map(|v| v.len()), Some(4));
+        assert!(staging.has_static());
+        staging.clear();
+
+        staging.load_static(&static_pixels(1, 4));
+        assert!(staging.take_static_upload().is_none());
+        assert!(staging.has_static());
+        staging.clear();
+        assert!(!staging.has_static());
+
+        staging.load_static(&static_pixels(2, 6));
+        assert_eq!(staging.take_static_upload().map(|v| v.len()), Some(6));
+        assert!(staging.take_static_upload().is_none());
+    }
+
+    #[test]
+    fn static_pixels_stay_out_of_the_stream() {
+        let mut staging = PolarStaging::new();
+        staging.load_static(&static_pixels(1, 4));
+        assert_eq!(staging.plan_stream(), StreamUpload::Nothing);
+        assert!(staging.get_stream_vertices().is_empty());
+    }
+}
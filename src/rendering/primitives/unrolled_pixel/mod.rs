@@ -0,0 +1,55 @@
+use gg::rendering::render_by_shaders::GliumStandardPrimitive;
+use gg::rendering::shaders::Shaders;
+use super::PolarPixel;
+mod unrolled_buffer;
+pub use self::unrolled_buffer::UnrolledBuffer;
+
+// A polar pixel drawn in the unrolled view, with angle across the screen and radius up it
+#[derive(Copy, Clone)]
+pub struct UnrolledPixel {
+    pub radial: [f64; 2],
+    pub angle: [f64; 2],
+    pub color: [f64; 4]
+}
+
+impl From<PolarPixel> for UnrolledPixel {
+    fn from(pol: PolarPixel) -> Self {
+        UnrolledPixel {
+            radial: pol.radial,
+            angle: pol.angle,
+            color: pol.color
+        }
+    }
+}
+
+impl GliumStandardPrimitive for UnrolledPixel {
+    type Vertex = UnrolledPixelVertex;
+
+    fn get_shaders() -> Shaders {
+        Shaders::VertexGeometryFragment(
+            include_str!("unrolled.vs"),
+            include_str!("unrolled.ges"),
+            include_str!("unrolled.fs"))
+    }
+
+    fn get_vertex(self) -> Vec<Self::Vertex> { vec![self.clone().into()] }
+}
+
+implement_vertex!(UnrolledPixelVertex, radial, angle, color);
+
+#[derive(Copy, Clone, Debug)]
+pub struct UnrolledPixelVertex {
+    pub radial: [f64; 2],
+    pub angle: [f64; 2],
+    pub color: [f64; 4]
+}
+
+impl From<UnrolledPixel> for UnrolledPixelVertex {
+    fn from(pix: UnrolledPixel) -> Self {
+        UnrolledPixelVertex {
+            radial: pix.radial,
+            angle: pix.angle,
+            color: pix.color
+        }
+    }
+}
@@ -0,0 +1,14 @@
+/*
+Unrolled Polar Fragment Shader
+ */
+
+#version 410 core
+
+in vec4 color_geom;
+
+out vec4 color;
+
+void main()
+{
+  color = color_geom;
+}
@@ -0,0 +1,69 @@
+use glium;
+use glium::{Display, Surface, Frame, DrawParameters, Blend};
+use glium::index::PrimitiveType;
+use super::{UnrolledPixel, UnrolledPixelVertex};
+use gg::rendering::glium_buffer::GliumBuffer;
+use gg::rendering::render_by_shaders::GliumStandardPrimitive;
+use gg::games::view_details::{ViewDetails, PolarViewDetails};
+use gg::rendering::shaders::make_program_from_shaders;
+
+pub struct UnrolledBuffer {
+    vertices: Vec<UnrolledPixelVertex>,
+    program: glium::Program,
+    primitive_type: PrimitiveType
+}
+
+impl GliumBuffer<UnrolledPixel> for UnrolledBuffer {
+    fn draw_at_target<Unif: glium::uniforms::Uniforms> (
+        &mut self,
+        target: &mut Frame,
+        display: &Display,
+        view_details: ViewDetails,
+        _: &DrawParameters,
+        _: &Unif,
+    ) {
+        if !self.vertices.is_empty() {
+            let polar_view: PolarViewDetails = match view_details {
+                ViewDetails::Polar(pol_view) => pol_view,
+                _ => panic!("Must use PolarViewDetails with unrolled pixel rendering elements!")
+            };
+            
+            let vertex_buffer = glium::VertexBuffer::new(display, &self.vertices).unwrap();
+
+            let uniforms = uniform! {
+                rotation_angle: polar_view.rotation_angle as f32,
+                radial_shift: polar_view.radial_shift as f32,
+                tunnel_mode: polar_view.tunnel_mode,
+                length_total: polar_view.length_total as f32,
+                length_circle: polar_view.length_circle as f32
+            };
+
+            let mut draw_params =  glium::draw_parameters::DrawParameters::default();
+            draw_params.blend = Blend::alpha_blending();
+            
+            target.draw(&vertex_buffer,
+                        &glium::index::NoIndices(self.primitive_type),
+                        &self.program,
+                        &uniforms,
+                        &draw_params).unwrap();
+        }
+    }
+
+    fn flush_buffer(&mut self) {
+        self.vertices = Vec::new();
+    }
+
+    fn get_vertices(&mut self) -> &mut Vec<UnrolledPixelVertex> {
+        &mut self.vertices
+    }
+}
+
+impl UnrolledBuffer {
+    pub fn new(display: &Display) -> Self {
+        UnrolledBuffer {
+            vertices: Vec::new(),
+            program: make_program_from_shaders(UnrolledPixel::get_shaders(), display),
+            primitive_type: UnrolledPixel::get_primitive_type(),
+        }
+    }
+}
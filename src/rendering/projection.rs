@@ -0,0 +1,50 @@
+/*
+CPU Versions of the Projection Performed by the Polar Shaders
+*/
+
+use gg::games::view_details::PolarViewDetails;
+use std::f64::consts::PI;
+
+// Matches render_radial in polar.vs, including the flat-mode radial shift
+pub fn render_radial(radial: f64, view: &PolarViewDetails) -> f64 {
+    let projected = if view.tunnel_mode {
+        let length_tunnel = view.length_total - view.length_circle;
+        if radial < view.length_circle {
+            radial / (view.length_circle * (length_tunnel + 1.0))
+        } else {
+            let radial_tunnel = (length_tunnel - (radial - view.length_circle)).max(-0.9);
+            1.0 - radial_tunnel / (radial_tunnel + 1.0)
+        }
+    } else {
+        radial - view.radial_shift
+    };
+    projected.max(0.0)
+}
+
+pub fn inverse_render_radial(screen_radius: f64, view: &PolarViewDetails) -> f64 {
+    if view.tunnel_mode {
+        let length_tunnel = view.length_total - view.length_circle;
+        if screen_radius < 1.0 / (length_tunnel + 1.0) {
+            screen_radius * view.length_circle * (length_tunnel + 1.0)
+        } else {
+            let radial_tunnel = 1.0 / screen_radius.max(0.000001) - 1.0;
+            view.length_circle + length_tunnel - radial_tunnel
+        }
+    } else {
+        screen_radius + view.radial_shift
+    }
+}
+
+// Converts a point in normalised screen coordinates (-1 to 1 on both axes) into world polar coordinates
+pub fn screen_to_polar(x: f64, y: f64, aspect_ratio: f64, view: &PolarViewDetails) -> (f64, f64) {
+    let x = x * aspect_ratio;
+    let screen_radius = (x * x + y * y).sqrt();
+    let screen_angle = y.atan2(x) / (2.0 * PI);
+    (inverse_render_radial(screen_radius, view), screen_angle + view.rotation_angle)
+}
+
+// The same conversion for the unrolled view, where x follows the angle and y the projected radius
+pub fn unrolled_to_polar(x: f64, y: f64, view: &PolarViewDetails) -> (f64, f64) {
+    let screen_radius = ((y + 1.0) / 2.0).max(0.0);
+    (inverse_render_radial(screen_radius, view), (x + 1.0) / 2.0 + view.rotation_angle - 0.75)
+}
@@ -0,0 +1,5 @@
+use rendering::{Renderable, PolarPrimitive, Background};
+
+impl Renderable<PolarPrimitive> for Background {
+    fn get_primitives(&mut self) -> Vec<PolarPrimitive> { vec![PolarPrimitive::Background(*self)] }
+}
\ No newline at end of file
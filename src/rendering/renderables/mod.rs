@@ -1,5 +1,8 @@
 pub mod polar_pixel;
+pub mod unrolled_pixel;
 pub mod text;
+pub mod background;
+pub mod post_effects;
 use ::rendering::primitives::PolarPrimitive;
 use gg::rendering::Renderable;
 
@@ -1,5 +1,15 @@
-use rendering::{Renderable, PolarPrimitive, PolarPixel};
+use rendering::{Renderable, PolarPrimitive, PolarPixel, StaticPixels, PolarPixelBatch};
+use std::mem;
 
 impl Renderable<PolarPrimitive> for PolarPixel {
     fn get_primitives(&mut self) -> Vec<PolarPrimitive> { vec![PolarPrimitive::PolarPix(self.clone())] }
+}
+
+impl Renderable<PolarPrimitive> for StaticPixels {
+    fn get_primitives(&mut self) -> Vec<PolarPrimitive> { vec![PolarPrimitive::StaticPolar(self.clone())] }
+}
+
+// Hands over the pixels without copying them
+impl Renderable<PolarPrimitive> for PolarPixelBatch {
+    fn get_primitives(&mut self) -> Vec<PolarPrimitive> { vec![PolarPrimitive::PolarBatch(mem::replace(&mut self.pixels, Vec::new()))] }
 }
\ No newline at end of file
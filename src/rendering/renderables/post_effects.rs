@@ -0,0 +1,6 @@
+use rendering::{Renderable, PolarPrimitive};
+use rendering::post_process::PostEffects;
+
+impl Renderable<PolarPrimitive> for PostEffects {
+    fn get_primitives(&mut self) -> Vec<PolarPrimitive> { vec![PolarPrimitive::PostEffects(*self)] }
+}
\ No newline at end of file
@@ -0,0 +1,5 @@
+use rendering::{Renderable, PolarPrimitive, UnrolledPixel};
+
+impl Renderable<PolarPrimitive> for UnrolledPixel {
+    fn get_primitives(&mut self) -> Vec<PolarPrimitive> { vec![PolarPrimitive::Unrolled(self.clone())] }
+}
\ No newline at end of file
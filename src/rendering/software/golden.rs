@@ -0,0 +1,210 @@
+/*
+Golden-Image Tests Rendering Fixed Scenes with the Software Renderer
+*/
+
+// Reference images live in tests/golden and a missing one fails its test. Setting UPDATE_GOLDEN=1
+// writes every reference from the current output, for new scenes and intended visual changes.
+
+use super::SoftwareRenderer;
+use super::png_file::{save_png, load_png};
+use ::polar_game::object::{Part, Point, Object};
+use ::polar_game::frame::PolarFrame;
+use ::polar_game::player::Player;
+use ::polar_game::flare::Flare;
+use ::polar_game::sun::Sun;
+use ::rendering::{PolarPixel, PixelFill, UnrolledPixel, PolarRenderable};
+use ::rendering::assets::TextureLayer;
+use gg::rendering::Renderer;
+use gg::games::view_details::{PolarViewDetails, ViewDetails};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 90;
+// A pixel differs if any channel moves by more than this, and a scene fails past the pixel budget
+const CHANNEL_TOLERANCE: u8 = 8;
+const DIFFERING_FRACTION: f64 = 0.001;
+
+fn tunnel_view() -> PolarViewDetails {
+    let mut view = PolarViewDetails::default();
+    view.tunnel_mode = true;
+    view.length_circle = 1.0;
+    view.length_total = 4.25;
+    view.radial_shift = 3.25;
+    view.rotation_angle = 0.0;
+    view
+}
+
+fn flat_view() -> PolarViewDetails {
+    let mut view = tunnel_view();
+    view.tunnel_mode = false;
+    view.radial_shift = 0.0;
+    view
+}
+
+fn frame_parts() -> Vec<Part> {
+    PolarFrame::new(0.5, 0.05, Point{x: 0.01, y: 0.02}, 8.0).get_render_parts()
+}
+
+fn player_parts(radial: f64) -> Vec<Part> {
+    Player::new(Point{x: radial, y: 0.2}, Point{x: 0.25, y: 0.03}).get_render_parts()
+}
+
+// One flare starting just below the seam and one wrapped to start just above it
+fn seam_flare_parts(radial: f64) -> Vec<Part> {
+    let mut parts = Vec::new();
+    for &(start, offset) in [(0.98, 0.0), (-0.01, 0.3)].iter() {
+        let mut flare = Flare::new(Point{x: 0.4, y: 0.04}, start, 0.0, 0);
+        flare.set_position(Point{x: radial + offset, y: 0.0});
+        parts.extend(flare.get_render_parts());
+    }
+    parts
+}
+
+fn sun_parts() -> Vec<Part> {
+    Sun::new(1.0).get_render_parts()
+}
+
+fn render(parts: Vec<Part>, view: PolarViewDetails, unrolled: bool) -> SoftwareRenderer {
+    let mut renderer = SoftwareRenderer::new(WIDTH, HEIGHT);
+    let renderables: Vec<Box<PolarRenderable>> = parts.into_iter().map(|p| -> Box<PolarRenderable> {
+        if unrolled { Box::new(UnrolledPixel::from(PolarPixel::from(p))) } else { Box::new(PolarPixel::from(p)) }
+    }).collect();
+    renderer.set_worldview(ViewDetails::Polar(view));
+    renderer.load_renderables(renderables);
+    renderer.render();
+    renderer
+}
+
+fn render_pixel(pixel: PolarPixel, textures: Vec<TextureLayer>) -> SoftwareRenderer {
+    let mut renderer = SoftwareRenderer::new(WIDTH, HEIGHT);
+    renderer.with_textures(textures);
+    renderer.set_worldview(ViewDetails::Polar(flat_view()));
+    renderer.load_renderables(vec![Box::new(pixel) as Box<PolarRenderable>]);
+    renderer.render();
+    renderer
+}
+
+fn check_golden(name: &str, renderer: &SoftwareRenderer) {
+    let path = format!("{}/tests/golden/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+    let actual = renderer.get_rgba();
+    if env::var("UPDATE_GOLDEN").is_ok() {
+        fs::create_dir_all(Path::new(&path).parent().unwrap()).expect("Failed to create the reference directory");
+        renderer.save_png(&path).expect("Failed to write reference image");
+        return;
+    }
+    assert!(Path::new(&path).exists(), "{} is missing; run with UPDATE_GOLDEN=1 to create it", path);
+
+    let (width, height, expected) = load_png(&path).expect("Failed to read reference image");
+    assert_eq!((width, height), (WIDTH, HEIGHT), "{} has the wrong size", path);
+    let differing = actual.chunks(4).zip(expected.chunks(4))
+        .filter(|&(a, e)| a.iter().zip(e.iter()).any(|(a, e)| (*a as i16 - *e as i16).abs() > CHANNEL_TOLERANCE as i16))
+        .count();
+    let fraction = differing as f64 / (WIDTH * HEIGHT) as f64;
+    if fraction > DIFFERING_FRACTION {
+        let actual_path = env::temp_dir().join(format!("{}.actual.png", name));
+        let actual_path = actual_path.to_string_lossy();
+        let _ = save_png(&actual_path, WIDTH, HEIGHT, &actual);
+        panic!("{}: {:.2}% of pixels differ from {} (output written to {})", name, fraction * 100.0, path, actual_path);
+    }
+}
+
+fn pixel(renderer: &SoftwareRenderer, x: u32, y: u32) -> [u8; 4] {
+    let rgba = renderer.get_rgba();
+    let i = ((y * WIDTH + x) * 4) as usize;
+    [rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3]]
+}
+
+// The pixel at a radius and angle in turns of the flat view
+fn pixel_at(renderer: &SoftwareRenderer, radius: f64, angle: f64) -> [u8; 4] {
+    let aspect_ratio = WIDTH as f64 / HEIGHT as f64;
+    let x = radius * (angle * 2.0 * ::std::f64::consts::PI).cos() / aspect_ratio;
+    let y = radius * (angle * 2.0 * ::std::f64::consts::PI).sin();
+    pixel(renderer, ((x + 1.0) / 2.0 * WIDTH as f64) as u32, ((1.0 - y) / 2.0 * HEIGHT as f64) as u32)
+}
+
+#[test]
+fn frame_grid_tunnel() {
+    check_golden("frame_grid_tunnel", &render(frame_parts(), tunnel_view(), false));
+}
+
+#[test]
+fn player_tunnel() {
+    let mut parts = frame_parts();
+    parts.extend(player_parts(4.0));
+    check_golden("player_tunnel", &render(parts, tunnel_view(), false));
+}
+
+#[test]
+fn seam_flares_tunnel() {
+    check_golden("seam_flares_tunnel", &render(seam_flare_parts(3.5), tunnel_view(), false));
+}
+
+#[test]
+fn sun_tunnel() {
+    check_golden("sun_tunnel", &render(sun_parts(), tunnel_view(), false));
+}
+
+#[test]
+fn full_scene_flat() {
+    let mut parts = frame_parts();
+    parts.extend(sun_parts());
+    parts.extend(seam_flare_parts(0.5));
+    parts.extend(player_parts(0.75));
+    check_golden("full_scene_flat", &render(parts, flat_view(), false));
+}
+
+#[test]
+fn seam_flares_unrolled() {
+    check_golden("seam_flares_unrolled", &render(seam_flare_parts(3.5), tunnel_view(), true));
+}
+
+// Independent of the references: a flare across the seam must light both sides of the positive x axis
+// and nothing on the opposite side of the disc
+#[test]
+fn seam_flare_covers_both_sides_of_the_seam() {
+    let mut flare = Flare::new(Point{x: 0.2, y: 0.04}, 0.98, 0.0, 0);
+    flare.set_position(Point{x: 0.6, y: 0.0});
+    let renderer = render(flare.get_render_parts(), flat_view(), false);
+    let black = [0, 0, 0, 255];
+    assert!(pixel_at(&renderer, 0.5, -0.01) != black, "below the seam is empty");
+    assert!(pixel_at(&renderer, 0.5, 0.01) != black, "above the seam is empty");
+    assert_eq!(pixel_at(&renderer, 0.5, 0.5), black, "the flare wrapped around the disc");
+}
+
+// The software port of the gradient fill runs from the pixel colour at the inner edge to the end colour outside
+#[test]
+fn radial_gradient_runs_from_inner_to_outer_edge() {
+    let pixel = PolarPixel {
+        radial: [0.2, 0.9],
+        angle: [0.0, 1.0],
+        color: [1.0, 0.0, 0.0, 1.0],
+        fill: PixelFill::RadialGradient([0.0, 0.0, 1.0, 1.0])
+    };
+    let renderer = render_pixel(pixel, Vec::new());
+    let inner = pixel_at(&renderer, 0.25, 0.25);
+    let outer = pixel_at(&renderer, 0.85, 0.25);
+    assert!(inner[0] > inner[2], "the inner edge is not mostly red: {:?}", inner);
+    assert!(outer[2] > outer[0], "the outer edge is not mostly blue: {:?}", outer);
+}
+
+// The left half of the layer is green and the right half white, tinted by the pixel colour
+#[test]
+fn textured_pixels_sample_their_layer() {
+    let rgba: Vec<u8> = (0..4).flat_map(|x| if x < 2 { vec![0, 255, 0, 255] } else { vec![255, 255, 255, 255] }).collect();
+    let layer = TextureLayer { width: 4, height: 1, rgba: rgba };
+    let pixel = PolarPixel {
+        radial: [0.2, 0.9],
+        angle: [0.0, 0.5],
+        color: [1.0, 1.0, 0.0, 1.0],
+        fill: PixelFill::Textured(0)
+    };
+    let renderer = render_pixel(pixel, vec![layer]);
+    assert_eq!(pixel_at(&renderer, 0.5, 0.1), [0, 255, 0, 255]);
+    assert_eq!(pixel_at(&renderer, 0.5, 0.4), [255, 255, 0, 255]);
+
+    // Without the layer the pixel falls back to its tint
+    let renderer = render_pixel(pixel, Vec::new());
+    assert_eq!(pixel_at(&renderer, 0.5, 0.1), [255, 255, 0, 255]);
+}
@@ -0,0 +1,151 @@
+/*
+CPU Renderer for Polar Primitives, for Headless Screenshots and Tests
+*/
+
+pub mod raster;
+pub mod text;
+pub mod png_file;
+#[cfg(test)]
+mod golden;
+pub use self::raster::ImageBuffer;
+
+use gg::rendering::Renderer;
+use gg::rendering::{WindowSpec, PlainText};
+use gg::games::view_details::{ViewDetails, PolarViewDetails};
+use gg::debug::*;
+use ::rendering::{PolarPixel, UnrolledPixel, Background, PolarPrimitive, PolarRenderable};
+use ::rendering::assets::TextureLayer;
+use glium::glutin::EventsLoop;
+use rusttype::Font;
+
+// Everything one frame draws, gathered from its renderables. Unlike the renderables it is plain data,
+// so a frame can be handed to another thread to be drawn
+#[derive(Default)]
+pub struct Scene {
+    background: Option<Background>,
+    polar_pixels: Vec<PolarPixel>,
+    unrolled_pixels: Vec<UnrolledPixel>,
+    texts: Vec<PlainText>,
+}
+
+impl Scene {
+    pub fn from_renderables(renderables: Vec<Box<PolarRenderable>>) -> Scene {
+        let mut scene = Scene::default();
+        scene.load(renderables);
+        scene
+    }
+
+    pub fn load(&mut self, renderables: Vec<Box<PolarRenderable>>) {
+        for mut renderable in renderables {
+            for primitive in renderable.get_primitives() {
+                match primitive {
+                    PolarPrimitive::Text(text) => self.texts.push(text),
+                    PolarPrimitive::PolarPix(polar) => self.polar_pixels.push(polar),
+                    PolarPrimitive::PolarBatch(pixels) => self.polar_pixels.extend(pixels),
+                    PolarPrimitive::StaticPolar(pixels) => self.polar_pixels.extend(pixels.pixels.iter().cloned()),
+                    PolarPrimitive::Unrolled(unrolled) => self.unrolled_pixels.push(unrolled),
+                    PolarPrimitive::Background(background) => self.background = Some(background),
+                    // Captures show the playfield as simulated, without the screen effects
+                    PolarPrimitive::PostEffects(_) => (),
+                }
+            }
+        }
+    }
+}
+
+pub struct SoftwareRenderer {
+    image: ImageBuffer,
+    font: Option<Font<'static>>,
+    background: Background,
+    textures: Vec<TextureLayer>,
+    view_details: PolarViewDetails,
+    scene: Scene,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> SoftwareRenderer {
+        SoftwareRenderer {
+            image: ImageBuffer::new(width, height),
+            font: None,
+            background: Background::default(),
+            textures: Vec::new(),
+            view_details: Default::default(),
+            scene: Scene::default(),
+        }
+    }
+
+    // Text is skipped without a font
+    pub fn with_font<'a>(&'a mut self, font: Font<'static>) -> &'a mut Self {
+        self.font = Some(font);
+        self
+    }
+
+    pub fn with_textures<'a>(&'a mut self, layers: Vec<TextureLayer>) -> &'a mut Self {
+        self.textures = layers;
+        self
+    }
+
+    pub fn load_scene(&mut self, scene: Scene) {
+        self.scene = scene;
+    }
+
+    pub fn get_image(&self) -> &ImageBuffer {
+        &self.image
+    }
+
+    pub fn get_rgba(&self) -> Vec<u8> {
+        self.image.to_rgba()
+    }
+
+    pub fn save_png(&self, path: &str) -> ::std::io::Result<()> {
+        let (width, height) = self.image.get_size();
+        png_file::save_png(path, width, height, &self.image.to_rgba())
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    type Primitive = PolarPrimitive;
+
+    fn load_renderables(&mut self, renderables: Vec<Box<PolarRenderable>>) {
+        self.scene.load(renderables);
+    }
+
+    // Draws in the same order as the glium renderer: polar pixels, unrolled pixels, then text
+    fn render(&mut self) {
+        debug_clock_start("Render::software_render");
+        if let Some(background) = self.scene.background.take() {
+            self.background = background;
+        }
+        let color = self.background.color;
+        self.image.clear([color[0] as f32, color[1] as f32, color[2] as f32, color[3] as f32]);
+        for pixel in self.scene.polar_pixels.drain(..) {
+            self.image.draw_polar(&pixel, &self.view_details, &self.textures);
+        }
+        for pixel in self.scene.unrolled_pixels.drain(..) {
+            self.image.draw_unrolled(&pixel, &self.view_details);
+        }
+        for text in self.scene.texts.drain(..) {
+            if let Some(ref font) = self.font {
+                text::draw_text(&mut self.image, font, &text);
+            }
+        }
+        debug_clock_stop("Render::software_render");
+    }
+
+    fn set_worldview(&mut self, view_details: ViewDetails) {
+        match view_details {
+            ViewDetails::Polar(view) => self.view_details = view,
+            _ => panic!("Must use PolarViewDetails with the software renderer!")
+        }
+    }
+
+    fn get_events_loop(&mut self) -> Option<&mut EventsLoop> {
+        None
+    }
+
+    fn get_window_spec(&self) -> WindowSpec {
+        WindowSpec {
+            aspect_ratio: self.image.get_aspect_ratio()
+        }
+    }
+}
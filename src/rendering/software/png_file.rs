@@ -0,0 +1,34 @@
+/*
+Reads and Writes 8-Bit RGBA PNG Files
+*/
+
+use png;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+
+fn to_io_error<E: ::std::fmt::Display>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+pub fn save_png(path: &str, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(to_io_error)?;
+    writer.write_image_data(rgba).map_err(to_io_error)
+}
+
+// Returns the width, height and RGBA bytes; other colour types are rejected rather than converted
+pub fn load_png(path: &str) -> io::Result<(u32, u32, Vec<u8>)> {
+    let decoder = png::Decoder::new(File::open(path)?);
+    let mut reader = decoder.read_info().map_err(to_io_error)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(to_io_error)?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not an 8-bit RGBA image", path)));
+    }
+    buffer.truncate(info.buffer_size());
+    Ok((info.width, info.height, buffer))
+}
@@ -0,0 +1,223 @@
+/*
+Rasterises Polar and Unrolled Pixels into an RGBA Buffer, Following the Polar Shaders
+*/
+
+use ::rendering::{PolarPixel, PixelFill, UnrolledPixel};
+use ::rendering::assets::TextureLayer;
+use ::rendering::projection::render_radial;
+use gg::games::view_details::PolarViewDetails;
+use std::f64::consts::PI;
+
+pub struct ImageBuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl ImageBuffer {
+    pub fn new(width: u32, height: u32) -> ImageBuffer {
+        ImageBuffer {
+            width: width,
+            height: height,
+            pixels: vec![[0.0, 0.0, 0.0, 1.0]; (width * height) as usize],
+        }
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn get_aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+
+    pub fn clear(&mut self, color: [f32; 4]) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel = color;
+        }
+    }
+
+    // Alpha blending as set up for the polar buffer: source alpha over one minus source alpha
+    pub fn blend(&mut self, x: i64, y: i64, color: [f32; 4]) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let pixel = &mut self.pixels[(y as u32 * self.width + x as u32) as usize];
+        let alpha = color[3].max(0.0).min(1.0);
+        for i in 0..4 {
+            pixel[i] = color[i] * alpha + pixel[i] * (1.0 - alpha);
+        }
+    }
+
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in self.pixels.iter() {
+            for channel in pixel.iter() {
+                bytes.push((channel.max(0.0).min(1.0) * 255.0).round() as u8);
+            }
+        }
+        bytes
+    }
+
+    // Pixel centres in normalised device coordinates, with y pointing up as in OpenGL
+    fn to_device(&self, x: i64, y: i64) -> (f64, f64) {
+        ((x as f64 + 0.5) / self.width as f64 * 2.0 - 1.0,
+         1.0 - (y as f64 + 0.5) / self.height as f64 * 2.0)
+    }
+
+    fn pixel_range(&self, min: (f64, f64), max: (f64, f64)) -> (i64, i64, i64, i64) {
+        let to_x = |x: f64| (x + 1.0) / 2.0 * self.width as f64;
+        let to_y = |y: f64| (1.0 - y) / 2.0 * self.height as f64;
+        let x_min = to_x(min.0).floor().max(0.0) as i64;
+        let x_max = to_x(max.0).ceil().min(self.width as f64) as i64;
+        let y_min = to_y(max.1).floor().max(0.0) as i64;
+        let y_max = to_y(min.1).ceil().min(self.height as f64) as i64;
+        (x_min, x_max, y_min, y_max)
+    }
+
+    // Textured fills fall back to their tint when the layer is missing
+    pub fn draw_polar(&mut self, pixel: &PolarPixel, view: &PolarViewDetails, textures: &[TextureLayer]) {
+        let aspect_ratio = self.get_aspect_ratio();
+        let radial = [render_radial(pixel.radial[0], view), render_radial(pixel.radial[1], view)];
+        let angle = [pixel.angle[0] - view.rotation_angle, pixel.angle[1] - view.rotation_angle];
+        let color = to_color(pixel.color);
+        let quad = sector_quad(radial, angle);
+
+        // Bounds are in the shader's fragment space, where x has been multiplied by the aspect ratio
+        let (min, max) = match quad {
+            Some(ref corners) => bounds(corners),
+            None => ((-radial[1], -radial[1]), (radial[1], radial[1])),
+        };
+        let (x_min, x_max, y_min, y_max) = self.pixel_range((min.0 / aspect_ratio, min.1), (max.0 / aspect_ratio, max.1));
+        let radial_square = (radial[0] * radial[0], radial[1] * radial[1]);
+
+        for y in y_min..y_max {
+            for x in x_min..x_max {
+                let (device_x, device_y) = self.to_device(x, y);
+                let frag = (device_x * aspect_ratio, device_y);
+                if let Some(ref corners) = quad {
+                    if !in_triangle(frag, corners[0], corners[1], corners[2])
+                        && !in_triangle(frag, corners[1], corners[2], corners[3]) {
+                        continue;
+                    }
+                }
+                let frag_radius = frag.0 * frag.0 + frag.1 * frag.1;
+                if frag_radius < radial_square.0 || frag_radius > radial_square.1 {
+                    continue;
+                }
+                let frag_angle = frag.1.atan2(frag.0) / (2.0 * PI);
+                if angle_compare(frag_angle, angle) {
+                    let color = match pixel.fill {
+                        PixelFill::Flat => color,
+                        _ => fill_color(pixel, frag_radius.sqrt(), frag_angle, radial, angle, textures),
+                    };
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+
+    pub fn draw_unrolled(&mut self, pixel: &UnrolledPixel, view: &PolarViewDetails) {
+        let y_small = 2.0 * render_radial(pixel.radial[0], view) - 1.0;
+        let y_large = 2.0 * render_radial(pixel.radial[1], view) - 1.0;
+        let color = to_color(pixel.color);
+        let start = pixel.angle[0] - view.rotation_angle + 0.75;
+        let width = pixel.angle[1] - pixel.angle[0];
+        if width >= 0.9999 {
+            self.fill_rect((-1.0, y_small), (1.0, y_large), color);
+            return;
+        }
+        let start = start - start.floor();
+        let end = start + width;
+        self.fill_rect((2.0 * start - 1.0, y_small), (2.0 * end.min(1.0) - 1.0, y_large), color);
+        if end > 1.0 {
+            self.fill_rect((-1.0, y_small), (2.0 * (end - 1.0) - 1.0, y_large), color);
+        }
+    }
+
+    fn fill_rect(&mut self, min: (f64, f64), max: (f64, f64), color: [f32; 4]) {
+        let (x_min, x_max, y_min, y_max) = self.pixel_range(min, max);
+        for y in y_min..y_max {
+            for x in x_min..x_max {
+                let (device_x, device_y) = self.to_device(x, y);
+                if device_x >= min.0 && device_x <= max.0 && device_y >= min.1 && device_y <= max.1 {
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+}
+
+// Port of fillColor in polar.fs, taking the projected radial and rotated angle ranges
+fn fill_color(pixel: &PolarPixel, radius: f64, frag_angle: f64, radial: [f64; 2], angle: [f64; 2],
+              textures: &[TextureLayer]) -> [f32; 4] {
+    let t = ((radius - radial[0]) / (radial[1] - radial[0]).max(0.00001)).max(0.0).min(1.0);
+    let from_start = frag_angle - angle[0];
+    let s = ((from_start - from_start.floor()) / (angle[1] - angle[0]).max(0.00001)).max(0.0).min(1.0);
+    let mix = |end: [f64; 4], f: f64| {
+        let c = pixel.color;
+        [c[0] + (end[0] - c[0]) * f, c[1] + (end[1] - c[1]) * f, c[2] + (end[2] - c[2]) * f, c[3] + (end[3] - c[3]) * f]
+    };
+    to_color(match pixel.fill {
+        PixelFill::Flat => pixel.color,
+        PixelFill::RadialGradient(end) => mix(end, t),
+        PixelFill::Textured(layer) => match textures.get(layer as usize) {
+            Some(texture) => {
+                let sample = texture.sample(s, t);
+                let c = pixel.color;
+                [sample[0] * c[0], sample[1] * c[1], sample[2] * c[2], sample[3] * c[3]]
+            },
+            None => pixel.color
+        }
+    })
+}
+
+fn to_color(color: [f64; 4]) -> [f32; 4] {
+    [color[0] as f32, color[1] as f32, color[2] as f32, color[3] as f32]
+}
+
+// The quad polar.ges emits around sectors up to a quarter turn wide, in the order it is stripped
+fn sector_quad(radial: [f64; 2], angle: [f64; 2]) -> Option<[(f64, f64); 4]> {
+    if angle[1] - angle[0] > 0.25 {
+        return None;
+    }
+    let first = angle[0] * 2.0 * PI;
+    let second = angle[1] * 2.0 * PI;
+    let radial_large = radial[1] / ((first - second) / 2.0).cos();
+    let radial_small = radial[0];
+    let corner = |radius: f64, angle: f64| (radius * angle.cos(), radius * angle.sin());
+    Some([corner(radial_large, first), corner(radial_small, first),
+          corner(radial_large, second), corner(radial_small, second)])
+}
+
+fn bounds(corners: &[(f64, f64); 4]) -> ((f64, f64), (f64, f64)) {
+    corners.iter().fold(((corners[0].0, corners[0].1), (corners[0].0, corners[0].1)), |(min, max), c| {
+        ((min.0.min(c.0), min.1.min(c.1)), (max.0.max(c.0), max.1.max(c.1)))
+    })
+}
+
+fn in_triangle(p: (f64, f64), a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> bool {
+    let cross = |o: (f64, f64), u: (f64, f64), v: (f64, f64)| (u.0 - o.0) * (v.1 - o.1) - (u.1 - o.1) * (v.0 - o.0);
+    let d1 = cross(a, b, p);
+    let d2 = cross(b, c, p);
+    let d3 = cross(c, a, p);
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
+
+// A line-for-line port of angleCompare in polar.fs, including the order the range is wrapped in
+pub fn angle_compare(angle: f64, range: [f64; 2]) -> bool {
+    let angle = angle - angle.floor();
+    if range[1] - range[0] >= 0.9999 {
+        return true;
+    }
+    let mut range = range;
+    range[1] -= range[0].floor();
+    range[0] -= range[1].floor();
+    if range[0] <= range[1] {
+        angle <= range[1] && angle >= range[0]
+    } else {
+        angle >= range[0] || angle <= range[1]
+    }
+}
@@ -0,0 +1,54 @@
+/*
+Draws Plain Text into the Software Image Buffer Using Rusttype
+*/
+
+use super::raster::ImageBuffer;
+use gg::rendering::{PlainText, TextAlign};
+use rusttype::{Font, Scale, point};
+use std::fs::File;
+use std::io::Read;
+
+pub fn load_font(path: &str) -> Option<Font<'static>> {
+    let mut bytes = Vec::new();
+    match File::open(path) {
+        Ok(mut f) => if f.read_to_end(&mut bytes).is_err() { return None; },
+        Err(_) => return None,
+    }
+    Font::try_from_vec(bytes)
+}
+
+// Positions are in normalised device coordinates and the scale is a fraction of the half-height,
+// matching how the HUD places its fixed text; text transforms are not applied
+pub fn draw_text(image: &mut ImageBuffer, font: &Font, text: &PlainText) {
+    let (width, height) = image.get_size();
+    let pixel_height = text.scale[1] as f32 * height as f32 / 2.0;
+    if pixel_height <= 0.0 {
+        return;
+    }
+    let scale = Scale::uniform(pixel_height);
+    let v_metrics = font.v_metrics(scale);
+
+    let glyphs: Vec<_> = font.layout(&text.content, scale, point(0.0, 0.0)).collect();
+    let text_width = glyphs.last()
+        .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+        .unwrap_or(0.0);
+
+    let center_x = (text.position[0] as f32 + 1.0) / 2.0 * width as f32;
+    let center_y = (1.0 - text.position[1] as f32) / 2.0 * height as f32;
+    let origin_x = match text.align {
+        TextAlign::Center => center_x - text_width / 2.0,
+        _ => center_x,
+    };
+    let baseline = center_y + (v_metrics.ascent + v_metrics.descent) / 2.0;
+    let color = [text.color[0] as f32, text.color[1] as f32, text.color[2] as f32, text.color[3] as f32];
+
+    for glyph in font.layout(&text.content, scale, point(origin_x, baseline)) {
+        if let Some(bounds) = glyph.pixel_bounding_box() {
+            glyph.draw(|x, y, coverage| {
+                let alpha = color[3] * coverage;
+                image.blend(bounds.min.x as i64 + x as i64, bounds.min.y as i64 + y as i64,
+                            [color[0], color[1], color[2], alpha]);
+            });
+        }
+    }
+}
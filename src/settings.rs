@@ -0,0 +1,283 @@
+/*
+Loads and Saves the Display, Gameplay and Input Settings File
+*/
+
+use gg::rendering::DisplaySettings;
+use polar_game::controls::{ControlBindings, ACTIONS};
+use polar_game::analogue::{StickSettings, ResponseCurve};
+use polar_game::mouse::MouseSettings;
+use polar_game::camera::CameraSettings;
+use polar_game::theme::Theme;
+use polar_game::trail::TrailSettings;
+use rendering::post_process::PostProcessSettings;
+use gg::debug::*;
+use toml;
+use std::fs::File;
+use std::io::{Read, Write};
+
+pub const DEFAULT_SETTINGS_PATH: &'static str = "settings.toml";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Settings {
+    pub display: DisplayConfig,
+    pub gameplay: GameplayConfig,
+    pub input: InputConfig,
+    pub camera: CameraSettings,
+    pub appearance: AppearanceConfig,
+    pub post_process: PostProcessSettings,
+    pub trails: TrailSettings,
+    pub capture: CaptureConfig,
+    pub controls: ControlBindings,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub text_glyph_detail: f32,
+    pub multisample_level: u16,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            width: 1920,
+            height: 1080,
+            fullscreen: true,
+            text_glyph_detail: 128.0,
+            multisample_level: 0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameplayConfig {
+    pub game_speed: f64,
+    pub hitbox_forgiveness: f64,
+    pub show_hitboxes: bool,
+    pub tunnel_mode: bool,
+    // Unranked runs that can be rewound with the rewind key
+    pub practice_mode: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    pub directory: String,
+    // Captures leave out text unless a TrueType font is given
+    pub font_path: String,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        CaptureConfig {
+            directory: "screenshots".to_string(),
+            font_path: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppearanceConfig {
+    // A built-in theme name or the name of a .toml file in the theme directory
+    pub theme: String,
+    pub theme_directory: String,
+    // Particle effect overrides, read at startup
+    pub effects_path: String,
+    // PNG images of equal size loaded at startup for textured pixels, one layer each in order,
+    // which a theme's frame_texture picks from
+    pub textures: Vec<String>,
+}
+
+impl Default for AppearanceConfig {
+    fn default() -> Self {
+        AppearanceConfig {
+            theme: "classic".to_string(),
+            theme_directory: "themes".to_string(),
+            effects_path: "effects.toml".to_string(),
+            textures: Vec::new(),
+        }
+    }
+}
+
+impl Default for GameplayConfig {
+    fn default() -> Self {
+        GameplayConfig {
+            game_speed: 1.0,
+            hitbox_forgiveness: 0.0,
+            show_hitboxes: false,
+            tunnel_mode: true,
+            practice_mode: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputConfig {
+    pub movement_speed: f64,
+    pub stick: StickSettings,
+    pub mouse: MouseSettings,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        InputConfig {
+            movement_speed: 0.3,
+            stick: StickSettings::default(),
+            mouse: MouseSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // Missing files give the defaults; broken sections and invalid values fall back with a warning
+    pub fn load(path: &str) -> Settings {
+        let mut content = String::new();
+        match File::open(path) {
+            Ok(mut f) => if let Err(e) = f.read_to_string(&mut content) {
+                warn(&format!("Failed to read settings file {}: {}", path, e));
+                return Settings::default();
+            },
+            Err(_) => return Settings::default(),
+        };
+
+        let value: toml::Value = match content.parse() {
+            Ok(v) => v,
+            Err(e) => {
+                warn(&format!("Failed to parse settings file {}: {}", path, e));
+                return Settings::default();
+            }
+        };
+
+        let mut settings = Settings {
+            display: load_section(&value, "display"),
+            gameplay: load_section(&value, "gameplay"),
+            input: load_section(&value, "input"),
+            camera: load_section(&value, "camera"),
+            appearance: load_section(&value, "appearance"),
+            post_process: load_section(&value, "post_process"),
+            trails: load_section(&value, "trails"),
+            capture: load_section(&value, "capture"),
+            controls: load_section(&value, "controls"),
+        };
+        for warning in settings.validate() {
+            warn(&warning);
+        }
+        settings
+    }
+
+    pub fn save(&self, path: &str) {
+        let content = match toml::to_string_pretty(self) {
+            Ok(c) => c,
+            Err(e) => { warn(&format!("Failed to serialise settings: {}", e)); return; }
+        };
+        match File::create(path) {
+            Ok(mut f) => if let Err(e) = f.write_all(content.as_bytes()) {
+                warn(&format!("Failed to write settings file {}: {}", path, e));
+            },
+            Err(e) => warn(&format!("Failed to create settings file {}: {}", path, e)),
+        }
+    }
+
+    // Resets any out-of-range values to their defaults, returning a warning for each one
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let display = DisplayConfig::default();
+        let gameplay = GameplayConfig::default();
+        let input = InputConfig::default();
+        let camera = CameraSettings::default();
+        let post_process = PostProcessSettings::default();
+        let trails = TrailSettings::default();
+
+        if self.display.width < 320 || self.display.height < 240 {
+            warnings.push(format!("Resolution {}x{} is too small, using {}x{}",
+                                  self.display.width, self.display.height, display.width, display.height));
+            self.display.width = display.width;
+            self.display.height = display.height;
+        }
+        check_range(&mut warnings, "display.text_glyph_detail", &mut self.display.text_glyph_detail, 8.0, 512.0, display.text_glyph_detail);
+        if ![0, 2, 4, 8, 16].contains(&self.display.multisample_level) {
+            warnings.push(format!("display.multisample_level {} is not one of 0, 2, 4, 8, 16, using {}",
+                                  self.display.multisample_level, display.multisample_level));
+            self.display.multisample_level = display.multisample_level;
+        }
+        check_range(&mut warnings, "gameplay.game_speed", &mut self.gameplay.game_speed, 0.25, 2.0, gameplay.game_speed);
+        check_range(&mut warnings, "gameplay.hitbox_forgiveness", &mut self.gameplay.hitbox_forgiveness, 0.0, 0.9, gameplay.hitbox_forgiveness);
+        check_range(&mut warnings, "input.movement_speed", &mut self.input.movement_speed, 0.05, 2.0, input.movement_speed);
+        let stick = &mut self.input.stick;
+        check_range(&mut warnings, "input.stick.radial_deadzone", &mut stick.radial_deadzone, 0.0, 0.9, input.stick.radial_deadzone);
+        check_range(&mut warnings, "input.stick.axial_deadzone", &mut stick.axial_deadzone, 0.0, 0.9, input.stick.axial_deadzone);
+        check_range(&mut warnings, "input.stick.x_axis.sensitivity", &mut stick.x_axis.sensitivity, 0.1, 5.0, input.stick.x_axis.sensitivity);
+        check_range(&mut warnings, "input.stick.y_axis.sensitivity", &mut stick.y_axis.sensitivity, 0.1, 5.0, input.stick.y_axis.sensitivity);
+        check_range(&mut warnings, "camera.smoothing", &mut self.camera.smoothing, 0.5, 30.0, camera.smoothing);
+        check_range(&mut warnings, "camera.rotation_drift", &mut self.camera.rotation_drift, -0.5, 0.5, camera.rotation_drift);
+        check_range(&mut warnings, "post_process.bloom_threshold", &mut self.post_process.bloom_threshold, 0.0, 1.0, post_process.bloom_threshold);
+        check_range(&mut warnings, "post_process.bloom_strength", &mut self.post_process.bloom_strength, 0.0, 4.0, post_process.bloom_strength);
+        if let Err(e) = Theme::find(&self.appearance.theme, &self.appearance.theme_directory) {
+            warnings.push(format!("{}, using the classic theme", e));
+            self.appearance.theme = AppearanceConfig::default().theme;
+        }
+        check_range(&mut warnings, "trails.interval", &mut self.trails.interval, 0.005, 0.5, trails.interval);
+        check_range(&mut warnings, "trails.player.length", &mut self.trails.player.length, 0, 64, trails.player.length);
+        check_range(&mut warnings, "trails.player.falloff", &mut self.trails.player.falloff, 0.0, 1.0, trails.player.falloff);
+        check_range(&mut warnings, "trails.flare.length", &mut self.trails.flare.length, 0, 64, trails.flare.length);
+        check_range(&mut warnings, "trails.flare.falloff", &mut self.trails.flare.falloff, 0.0, 1.0, trails.flare.falloff);
+        let custom_curve_valid = match stick.curve {
+            ResponseCurve::Custom(ref points) => points.len() >= 2 && points.iter().all(|p| *p >= 0.0 && *p <= 1.0),
+            _ => true
+        };
+        if !custom_curve_valid {
+            warnings.push("input.stick.curve needs at least two points between 0 and 1, using linear".to_string());
+            stick.curve = ResponseCurve::Linear;
+        }
+        let controls = ControlBindings::default();
+        for action in ACTIONS.iter() {
+            if self.controls.get(*action).is_empty() {
+                warnings.push(format!("No bindings for {:?}, using the defaults", action));
+                *self.controls.get_mut(*action) = controls.get(*action).clone();
+            }
+        }
+        warnings
+    }
+
+    pub fn get_display_settings(&self) -> DisplaySettings {
+        DisplaySettings {
+            res: (self.display.width, self.display.height),
+            fullscreen: self.display.fullscreen,
+            text_glyph_detail: self.display.text_glyph_detail,
+            multisample_level: self.display.multisample_level,
+            ..Default::default()
+        }
+    }
+}
+
+fn load_section<T: Default + ::serde::de::DeserializeOwned>(value: &toml::Value, name: &str) -> T {
+    match value.get(name) {
+        Some(section) => match section.clone().try_into() {
+            Ok(s) => s,
+            Err(e) => {
+                warn(&format!("Invalid [{}] settings, using defaults: {}", name, e));
+                T::default()
+            }
+        },
+        None => T::default(),
+    }
+}
+
+fn check_range<T: PartialOrd + Copy + ::std::fmt::Display>(warnings: &mut Vec<String>, name: &str, value: &mut T, min: T, max: T, default: T) {
+    if !(*value >= min && *value <= max) {
+        warnings.push(format!("{} {} is outside {}..{}, using {}", name, *value, min, max, default));
+        *value = default;
+    }
+}
+
+// For problems in the files players edit by hand, which they should see without a debug build
+pub fn warn(message: &str) {
+    println!("Warning: {}", message);
+    debug(message);
+}